@@ -0,0 +1,29 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+
+/// Exposes the locked versions of dependencies as `<NAME>_VERSION` environment variables,
+/// so they can be reported by `evtx.build_info()`.
+fn main() {
+    let lockfile = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("Cargo.lock");
+    println!("cargo:rerun-if-changed={}", lockfile.display());
+
+    let lock = fs::read_to_string(&lockfile).unwrap_or_default();
+
+    for (package, var) in [("evtx", "EVTX_RS_VERSION"), ("pyo3", "PYO3_VERSION")] {
+        let version = locked_version(&lock, package).unwrap_or("unknown");
+        println!("cargo:rustc-env={}={}", var, version);
+    }
+}
+
+fn locked_version<'a>(lock: &'a str, package: &str) -> Option<&'a str> {
+    let name = format!("name = \"{}\"", package);
+
+    lock.split("[[package]]").find_map(|entry| {
+        let mut lines = entry.lines().map(str::trim);
+        lines.find(|line| *line == name)?;
+        lines
+            .find_map(|line| line.strip_prefix("version = "))
+            .map(|version| version.trim_matches('"'))
+    })
+}
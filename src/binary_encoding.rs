@@ -0,0 +1,164 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use evtx_rs::binxml::value_variant::BinXmlValue;
+use evtx_rs::model::deserialized::BinXMLDeserializedTokens;
+use evtx_rs::EvtxRecord;
+use pyo3::exceptions::PyValueError;
+use pyo3::PyErr;
+
+use crate::walk::{self, Node};
+
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::ops::ControlFlow;
+
+/// Noncharacters (reserved for internal use by Unicode) marking the bounds of CDATA sections,
+/// which `evtx` would escape if they were written as is.
+const CDATA_START: char = '\u{FDD0}';
+const CDATA_END: char = '\u{FDD1}';
+
+/// Controls how binary values are rendered in serialized records.
+#[derive(Copy, Clone, Debug, PartialOrd, PartialEq, Eq)]
+pub enum BinaryEncoding {
+    /// Upper case hex digits, as rendered by `evtx`.
+    Hex,
+    /// Standard base64, with padding.
+    Base64,
+    /// Upper case hex digits in a CDATA section in XML records, setting them apart from strings.
+    /// Attributes can't hold a CDATA section, and JSON has no such thing,
+    /// so binary values in attributes and in JSON records are plain hex digits.
+    Cdata,
+}
+
+impl BinaryEncoding {
+    pub fn from_name(name: &str) -> Result<BinaryEncoding, PyErr> {
+        match name {
+            "hex" => Ok(BinaryEncoding::Hex),
+            "base64" => Ok(BinaryEncoding::Base64),
+            "cdata" => Ok(BinaryEncoding::Cdata),
+            _ => Err(PyErr::new::<PyValueError, _>(format!(
+                "Unknown binary encoding `[{}]`, possible values are: hex, base64, cdata",
+                name
+            ))),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            BinaryEncoding::Hex => "hex",
+            BinaryEncoding::Base64 => "base64",
+            BinaryEncoding::Cdata => "cdata",
+        }
+    }
+
+    /// Replaces the binary values of `record` with their encoded form, ahead of serialization
+    /// (to XML if `xml` is set). CDATA sections are only marked: this returns whether
+    /// the serialized record must go through `finish`.
+    pub fn apply(self, record: &mut EvtxRecord, xml: bool) -> bool {
+        let cdata = match self {
+            BinaryEncoding::Hex => return false,
+            BinaryEncoding::Cdata if !xml => return false,
+            BinaryEncoding::Cdata => match cdata_values(record) {
+                Some(values) if !values.is_empty() => values,
+                _ => return false,
+            },
+            BinaryEncoding::Base64 => HashSet::new(),
+        };
+
+        self.encode_values(&mut record.tokens, &cdata);
+        !cdata.is_empty()
+    }
+
+    /// Turns the marks left by `apply` in a serialized XML record into CDATA sections.
+    pub fn finish(data: String) -> String {
+        data.replace(CDATA_START, "<![CDATA[")
+            .replace(CDATA_END, "]]>")
+    }
+
+    fn encode_values(self, tokens: &mut [BinXMLDeserializedTokens], cdata: &HashSet<*const ()>) {
+        for token in tokens {
+            match token {
+                BinXMLDeserializedTokens::Value(value) => self.encode_value(value, cdata),
+                BinXMLDeserializedTokens::TemplateInstance(template) => {
+                    self.encode_values(&mut template.substitution_array, cdata)
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn encode_value(self, value: &mut BinXmlValue, cdata: &HashSet<*const ()>) {
+        let address = address(value);
+
+        match value {
+            BinXmlValue::BinXmlType(tokens) => self.encode_values(tokens, cdata),
+            BinXmlValue::BinaryType(bytes) if cdata.contains(&address) => {
+                let mut encoded = String::with_capacity(bytes.len() * 2 + 2);
+                encoded.push(CDATA_START);
+                for b in bytes.iter() {
+                    let _ = write!(encoded, "{:02X}", b);
+                }
+                encoded.push(CDATA_END);
+                *value = BinXmlValue::StringType(encoded);
+            }
+            BinXmlValue::BinaryType(bytes) if self == BinaryEncoding::Base64 => {
+                *value = BinXmlValue::StringType(STANDARD.encode(bytes));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Identifies a value of a record between walking its tokens and encoding them,
+/// as a substitution value is rendered wherever its template refers to it.
+fn address(value: &BinXmlValue) -> *const () {
+    value as *const BinXmlValue as *const ()
+}
+
+/// Finds the binary values of `record` which are written as element content only
+/// (a CDATA section can't be written in an attribute).
+///
+/// Returns `None` if the record can't be walked, or if one of its names or strings holds
+/// a character marking CDATA sections, which `finish` would take for a mark.
+fn cdata_values(record: &EvtxRecord) -> Option<HashSet<*const ()>> {
+    let (mut in_content, mut in_attributes) = (HashSet::new(), HashSet::new());
+    let mut reserved = false;
+
+    walk::walk(record, |node| {
+        let (name, value, positions) = match node {
+            Node::Element(name, _) => (Some(name), None, None),
+            Node::Attribute(name, value, _) => (Some(name), Some(value), Some(&mut in_attributes)),
+            Node::Text(value, _) => (None, Some(value), Some(&mut in_content)),
+            Node::End(_) => return ControlFlow::Continue(()),
+        };
+
+        reserved = name.is_some_and(|name| {
+            walk::name(record.chunk, name).is_some_and(|name| has_marks(name.as_str()))
+        }) || value.is_some_and(|value| match value {
+            BinXmlValue::StringType(s) => has_marks(s),
+            BinXmlValue::AnsiStringType(s) => has_marks(s),
+            BinXmlValue::StringArrayType(strings) => strings.iter().any(|s| has_marks(s)),
+            _ => false,
+        });
+        if reserved {
+            return ControlFlow::Break(());
+        }
+
+        if let (Some(value @ BinXmlValue::BinaryType(_)), Some(positions)) = (value, positions) {
+            positions.insert(address(value));
+        }
+        ControlFlow::Continue(())
+    })
+    .ok()?;
+
+    if reserved {
+        return None;
+    }
+
+    in_content.retain(|value| !in_attributes.contains(value));
+    Some(in_content)
+}
+
+fn has_marks(s: &str) -> bool {
+    s.contains([CDATA_START, CDATA_END])
+}
@@ -0,0 +1,56 @@
+use std::convert::TryInto;
+
+const CHECKPOINT_MAGIC: &[u8; 8] = b"EvtxCkpt";
+const CHECKPOINT_VERSION: u8 = 1;
+const CHECKPOINT_SIZE: usize = 8 + 1 + 4 + 8 + 8 + 8;
+
+/// The position of the last record yielded by an iterator, from which iteration can be resumed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Checkpoint {
+    /// The index of the file, when parsing several files stored back to back.
+    pub file_index: u32,
+    pub chunk_number: u64,
+    /// The first record id of the chunk, as stored in its header.
+    /// Used to tell whether the chunk is still the same when resuming.
+    pub chunk_first_record_id: u64,
+    pub record_id: u64,
+}
+
+impl Checkpoint {
+    pub fn to_bytes(self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(CHECKPOINT_SIZE);
+
+        bytes.extend_from_slice(CHECKPOINT_MAGIC);
+        bytes.push(CHECKPOINT_VERSION);
+        bytes.extend_from_slice(&self.file_index.to_le_bytes());
+        bytes.extend_from_slice(&self.chunk_number.to_le_bytes());
+        bytes.extend_from_slice(&self.chunk_first_record_id.to_le_bytes());
+        bytes.extend_from_slice(&self.record_id.to_le_bytes());
+
+        bytes
+    }
+
+    /// Returns `None` if `bytes` were not produced by `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Checkpoint> {
+        if bytes.len() != CHECKPOINT_SIZE
+            || &bytes[..8] != CHECKPOINT_MAGIC
+            || bytes[8] != CHECKPOINT_VERSION
+        {
+            return None;
+        }
+
+        let u64_at = |offset: usize| {
+            bytes[offset..offset + 8]
+                .try_into()
+                .ok()
+                .map(u64::from_le_bytes)
+        };
+
+        Some(Checkpoint {
+            file_index: u32::from_le_bytes(bytes[9..13].try_into().ok()?),
+            chunk_number: u64_at(13)?,
+            chunk_first_record_id: u64_at(21)?,
+            record_id: u64_at(29)?,
+        })
+    }
+}
@@ -0,0 +1,46 @@
+use evtx_rs::EvtxChunkData;
+
+use crate::raw_records::EVTX_CHUNK_HEADER_SIZE;
+
+/// `ChunkFlags::NO_CRC32`, which `evtx` does not export.
+const CHUNK_FLAG_NO_CRC32: u32 = 0x4;
+
+/// The header and events checksums of a chunk, as stored in its header and as computed from its data.
+///
+/// Both are reported as `0` for chunks flagged as having no checksums, the same as `evtx` does.
+pub struct ChunkChecksums {
+    pub stored: (u32, u32),
+    pub computed: (u32, u32),
+}
+
+impl ChunkChecksums {
+    pub fn from_chunk(chunk: &EvtxChunkData) -> ChunkChecksums {
+        if chunk.header.flags.bits() & CHUNK_FLAG_NO_CRC32 != 0 {
+            return ChunkChecksums {
+                stored: (0, 0),
+                computed: (0, 0),
+            };
+        }
+
+        let mut header = crc32fast::Hasher::new();
+        header.update(&chunk.data[..120]);
+        header.update(&chunk.data[128..EVTX_CHUNK_HEADER_SIZE]);
+
+        // A corrupt free space offset must not take us out of the chunk.
+        let events_end = (chunk.header.free_space_offset as usize)
+            .clamp(EVTX_CHUNK_HEADER_SIZE, chunk.data.len());
+        let events = crc32fast::hash(&chunk.data[EVTX_CHUNK_HEADER_SIZE..events_end]);
+
+        ChunkChecksums {
+            stored: (
+                chunk.header.header_chunk_checksum,
+                chunk.header.events_checksum,
+            ),
+            computed: (header.finalize(), events),
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.stored == self.computed
+    }
+}
@@ -0,0 +1,200 @@
+use evtx_rs::binxml::deserializer::BinXmlDeserializer;
+use evtx_rs::binxml::value_variant::BinXmlValue;
+use evtx_rs::err::{ChunkError, EvtxError};
+use evtx_rs::model::deserialized::BinXMLDeserializedTokens;
+use evtx_rs::{EvtxChunk, EvtxChunkData, EvtxRecord, EvtxRecordHeader, ParserSettings};
+use self_cell::self_cell;
+
+use crate::raw_records::{LocatedHeader, RecordHeaders};
+
+use std::sync::Arc;
+
+self_cell!(
+    /// A chunk, along with the string and template caches which borrow its data.
+    struct ParsedChunk {
+        owner: EvtxChunkData,
+
+        #[covariant]
+        dependent: EvtxChunk,
+    }
+);
+
+/// The records of a chunk, deserialized one at a time (unlike `EvtxChunk::iter`, this owns the chunk,
+/// so it can be kept between calls to `next()` of a Python iterator).
+pub struct ChunkRecords {
+    chunk: ParsedChunk,
+    settings: Arc<ParserSettings>,
+    /// Settings with other ANSI codecs, to retry records which fail to deserialize with.
+    fallback_settings: Vec<Arc<ParserSettings>>,
+    headers: RecordHeaders,
+    index: usize,
+}
+
+impl ChunkRecords {
+    #[allow(clippy::result_large_err)]
+    pub fn new(data: EvtxChunkData, settings: Arc<ParserSettings>) -> Result<Self, ChunkError> {
+        let headers = RecordHeaders::new(&data.header);
+        let chunk = ParsedChunk::try_new(data, |data| {
+            EvtxChunk::new(&data.data, &data.header, Arc::clone(&settings))
+        })?;
+
+        Ok(ChunkRecords {
+            chunk,
+            settings,
+            fallback_settings: Vec::new(),
+            headers,
+            index: 0,
+        })
+    }
+
+    /// Retries records whose ANSI strings fail to decode, or decode to replacement characters,
+    /// with each of `fallback_settings` in turn, for files whose ANSI strings
+    /// are not all in the same encoding.
+    pub fn with_fallback_settings(mut self, fallback_settings: Vec<Arc<ParserSettings>>) -> Self {
+        self.fallback_settings = fallback_settings;
+        self
+    }
+
+    /// Whether iteration stopped at an incomplete record at the end of a dirty chunk
+    /// (such as the active chunk of a file copied from a running system).
+    pub fn truncated(&self) -> bool {
+        self.headers.truncated()
+    }
+
+    /// Deserializes the next record in the same manner as `EvtxChunk::iter`, and calls `f` with it,
+    /// its index within the chunk and its raw bytes (including its header).
+    ///
+    /// The record borrows the chunk, so it can only be used inside `f`.
+    #[allow(clippy::result_large_err)]
+    pub fn next_with<T>(
+        &mut self,
+        f: impl FnOnce(Result<EvtxRecord, EvtxError>, usize, &[u8]) -> T,
+    ) -> Option<T> {
+        let ChunkRecords {
+            chunk,
+            settings,
+            fallback_settings,
+            headers,
+            index,
+        } = self;
+
+        chunk.with_dependent(|_, chunk| {
+            let LocatedHeader {
+                header,
+                offset,
+                binxml_offset,
+            } = match headers.next(chunk.header, chunk.data)? {
+                Ok(located) => located,
+                Err(e) => return Some(f(Err(EvtxError::DeserializationError(e)), *index, &[])),
+            };
+
+            let record = read_record_with_fallbacks(
+                chunk,
+                &header,
+                binxml_offset,
+                settings,
+                fallback_settings,
+            );
+
+            let data = &chunk.data[offset as usize..];
+            let data = &data[..data.len().min(header.data_size as usize)];
+
+            let result = f(record, *index, data);
+            *index += 1;
+
+            Some(result)
+        })
+    }
+}
+
+/// Reads a record with `settings`, then with each of `fallback_settings` until one decodes
+/// its ANSI strings without replacement characters. Failing that, the first successful read
+/// is returned.
+#[allow(clippy::result_large_err)]
+fn read_record_with_fallbacks<'a>(
+    chunk: &'a EvtxChunk<'a>,
+    header: &EvtxRecordHeader,
+    binxml_offset: u64,
+    settings: &Arc<ParserSettings>,
+    fallback_settings: &[Arc<ParserSettings>],
+) -> Result<EvtxRecord<'a>, EvtxError> {
+    let mut record = read_record(chunk, header, binxml_offset, settings);
+    if record
+        .as_ref()
+        .is_ok_and(|record| !has_replacement_chars(record))
+    {
+        return record;
+    }
+
+    for settings in fallback_settings {
+        match read_record(chunk, header, binxml_offset, settings) {
+            Ok(retried) if !has_replacement_chars(&retried) => return Ok(retried),
+            Ok(retried) if record.is_err() => record = Ok(retried),
+            _ => {}
+        }
+    }
+
+    record
+}
+
+/// Whether an ANSI string of the record decoded to replacement characters, which happens
+/// when the codec doesn't fit the string even though decoding didn't fail.
+fn has_replacement_chars(record: &EvtxRecord) -> bool {
+    let mut pending = vec![record.tokens.as_slice()];
+
+    while let Some(tokens) = pending.pop() {
+        for token in tokens {
+            match token {
+                BinXMLDeserializedTokens::Value(BinXmlValue::AnsiStringType(s))
+                    if s.contains(char::REPLACEMENT_CHARACTER) =>
+                {
+                    return true
+                }
+                BinXMLDeserializedTokens::Value(BinXmlValue::BinXmlType(tokens)) => {
+                    pending.push(tokens)
+                }
+                BinXMLDeserializedTokens::TemplateInstance(template) => {
+                    pending.push(&template.substitution_array)
+                }
+                _ => {}
+            }
+        }
+    }
+
+    false
+}
+
+#[allow(clippy::result_large_err)]
+fn read_record<'a>(
+    chunk: &'a EvtxChunk<'a>,
+    header: &EvtxRecordHeader,
+    binxml_offset: u64,
+    settings: &Arc<ParserSettings>,
+) -> Result<EvtxRecord<'a>, EvtxError> {
+    let failed_to_parse = |e| EvtxError::FailedToParseRecord {
+        record_id: header.event_record_id,
+        source: Box::new(EvtxError::DeserializationError(e)),
+    };
+
+    let deserializer = BinXmlDeserializer::init(
+        chunk.data,
+        binxml_offset,
+        Some(chunk),
+        false,
+        settings.get_ansi_codec(),
+    );
+
+    let tokens = deserializer
+        .iter_tokens(Some(header.record_data_size()))
+        .map_err(failed_to_parse)?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(failed_to_parse)?;
+
+    Ok(EvtxRecord {
+        chunk,
+        event_record_id: header.event_record_id,
+        timestamp: header.timestamp,
+        tokens,
+        settings: Arc::clone(settings),
+    })
+}
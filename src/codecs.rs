@@ -0,0 +1,67 @@
+use encoding::all::encodings;
+use encoding::EncodingRef;
+
+/// Python codec names and common IANA aliases, keyed by the `encoding` crate name they map to.
+/// Aliases are compared with case and separators (`-`, `_`, spaces) ignored.
+const ALIASES: &[(&str, &[&str])] = &[
+    ("ascii", &["usascii", "646"]),
+    ("ibm866", &["cp866", "866"]),
+    (
+        "iso-8859-1",
+        &["latin", "latin1", "l1", "8859", "cp819", "isoir100"],
+    ),
+    ("iso-8859-2", &["latin2", "l2", "isoir101"]),
+    ("iso-8859-3", &["latin3", "l3", "isoir109"]),
+    ("iso-8859-4", &["latin4", "l4", "isoir110"]),
+    ("iso-8859-5", &["cyrillic", "isoir144"]),
+    ("iso-8859-6", &["arabic", "isoir127"]),
+    ("iso-8859-7", &["greek", "greek8", "isoir126"]),
+    ("iso-8859-8", &["hebrew", "isoir138"]),
+    ("iso-8859-10", &["latin6", "l6", "isoir157"]),
+    ("iso-8859-13", &["latin7", "l7"]),
+    ("iso-8859-14", &["latin8", "l8", "isoceltic"]),
+    ("iso-8859-15", &["latin9", "l9"]),
+    ("iso-8859-16", &["latin10", "l10"]),
+    ("mac-roman", &["macintosh"]),
+    ("windows-874", &["cp874"]),
+    ("windows-1250", &["cp1250"]),
+    ("windows-1251", &["cp1251"]),
+    ("windows-1252", &["cp1252"]),
+    ("windows-1253", &["cp1253"]),
+    ("windows-1254", &["cp1254"]),
+    ("windows-1255", &["cp1255"]),
+    ("windows-1256", &["cp1256"]),
+    ("windows-1257", &["cp1257"]),
+    ("windows-1258", &["cp1258"]),
+    ("utf-8", &["utf", "u8", "cp65001"]),
+    ("windows-949", &["cp949", "uhc", "euckr"]),
+    (
+        "windows-31j",
+        &["cp932", "ms932", "mskanji", "shiftjis", "sjis"],
+    ),
+    ("gbk", &["cp936", "ms936", "936", "gb2312"]),
+    ("hz", &["hzgb2312", "hzgb"]),
+    ("big5-2003", &["big5", "big5tw", "csbig5", "cp950"]),
+];
+
+/// Looks up an encoding by its `encoding` crate name, or by one of its aliases.
+pub fn ansi_codec_from_name(name: &str) -> Option<EncodingRef> {
+    let normalized = normalize(name);
+
+    let name = ALIASES
+        .iter()
+        .find(|(_, aliases)| aliases.contains(&normalized.as_str()))
+        .map_or(name, |(name, _)| name);
+
+    encodings()
+        .iter()
+        .find(|c| c.name() == name || normalize(c.name()) == normalized)
+        .copied()
+}
+
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| !matches!(c, '-' | '_' | ' '))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
@@ -0,0 +1,70 @@
+use crate::values::{json_to_pyobject, to_pyobject};
+use pyo3::types::{PyList, PyListMethods};
+use pyo3::{Bound, PyResult, Python};
+use serde_json::Value;
+
+/// The type all the values of a column are converted to, so that columnar libraries
+/// (such as pandas or pyarrow) don't have to fall back to generic objects.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ColumnType {
+    Bool,
+    Int,
+    Float,
+    /// Strings, and JSON text for mixed or nested values.
+    Str,
+}
+
+impl ColumnType {
+    /// The narrowest type holding every value of the column (missing values are ignored).
+    pub fn infer(values: &[Option<Value>]) -> ColumnType {
+        let mut present = values.iter().flatten().filter(|value| !value.is_null());
+
+        let first = match present.next() {
+            Some(first) => first,
+            None => return ColumnType::Str,
+        };
+
+        present.fold(ColumnType::of(first), |column_type, value| {
+            match (column_type, ColumnType::of(value)) {
+                (a, b) if a == b => a,
+                (ColumnType::Int, ColumnType::Float) | (ColumnType::Float, ColumnType::Int) => {
+                    ColumnType::Float
+                }
+                _ => ColumnType::Str,
+            }
+        })
+    }
+
+    fn of(value: &Value) -> ColumnType {
+        match value {
+            Value::Bool(_) => ColumnType::Bool,
+            Value::Number(n) if n.is_f64() => ColumnType::Float,
+            Value::Number(_) => ColumnType::Int,
+            _ => ColumnType::Str,
+        }
+    }
+}
+
+/// Converts `values` to a list of `column_type` values, with `None` for missing values.
+pub fn column_to_pylist<'py>(
+    py: Python<'py>,
+    values: &[Option<Value>],
+    column_type: ColumnType,
+) -> PyResult<Bound<'py, PyList>> {
+    let list = PyList::empty(py);
+
+    for value in values {
+        let value = match value {
+            None | Some(Value::Null) => py.None(),
+            Some(Value::Number(n)) if column_type == ColumnType::Float => {
+                to_pyobject(py, n.as_f64())?
+            }
+            Some(Value::String(s)) => to_pyobject(py, s)?,
+            Some(value) if column_type == ColumnType::Str => to_pyobject(py, value.to_string())?,
+            Some(value) => json_to_pyobject(py, value)?,
+        };
+        list.append(value)?;
+    }
+
+    Ok(list)
+}
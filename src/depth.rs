@@ -0,0 +1,79 @@
+use evtx_rs::binxml::value_variant::BinXmlValue;
+use evtx_rs::model::deserialized::BinXMLDeserializedTokens;
+use evtx_rs::EvtxRecord;
+
+use std::collections::HashSet;
+
+/// Bound on the tokens of an expanded record, which only templates instantiating other
+/// templates many times over reach (a record can't be larger than its 64KiB chunk).
+const MAX_EXPANDED_TOKENS: usize = 1 << 20;
+
+/// Returns whether rendering the record would nest deeper than `max_depth`
+/// (or expand to more than a million tokens, which `evtx` would take forever to render).
+///
+/// Depth is counted in elements, plus one for every template instance and nested BinXml value,
+/// so that self-referencing templates are bounded as well.
+/// The tokens are walked without recursion, as they may come from an untrusted file.
+pub fn exceeds_depth(record: &EvtxRecord, max_depth: usize) -> bool {
+    let no_substitutions: &[BinXMLDeserializedTokens] = &[];
+    let mut pending = vec![(record.tokens.as_slice(), no_substitutions, 0_usize)];
+    // Like `evtx`, which moves a substitution value out of its template instance when rendering
+    // it, a value is only expanded where it's first referred to, which keeps this linear.
+    let mut substituted = HashSet::new();
+    let mut expanded_tokens = 0_usize;
+
+    while let Some((tokens, substitutions, mut depth)) = pending.pop() {
+        expanded_tokens += tokens.len();
+        if expanded_tokens > MAX_EXPANDED_TOKENS {
+            return true;
+        }
+
+        for token in tokens {
+            let nested = match token {
+                BinXMLDeserializedTokens::OpenStartElement(_) => {
+                    depth += 1;
+                    None
+                }
+                BinXMLDeserializedTokens::CloseEmptyElement
+                | BinXMLDeserializedTokens::CloseElement => {
+                    depth = depth.saturating_sub(1);
+                    None
+                }
+                BinXMLDeserializedTokens::TemplateInstance(template) => record
+                    .chunk
+                    .template_table
+                    .get_template(template.template_def_offset)
+                    .map(|definition| {
+                        (
+                            definition.tokens.as_slice(),
+                            template.substitution_array.as_slice(),
+                            depth + 1,
+                        )
+                    }),
+                BinXMLDeserializedTokens::Value(BinXmlValue::BinXmlType(tokens)) => {
+                    Some((tokens.as_slice(), no_substitutions, depth + 1))
+                }
+                BinXMLDeserializedTokens::Substitution(descriptor) if !descriptor.ignore => {
+                    substitutions
+                        .get(usize::from(descriptor.substitution_index))
+                        .filter(|&value| substituted.insert(value as *const _))
+                        .map(|value| (std::slice::from_ref(value), no_substitutions, depth))
+                }
+                _ => None,
+            };
+
+            if depth > max_depth {
+                return true;
+            }
+
+            if let Some(nested) = nested {
+                if nested.2 > max_depth {
+                    return true;
+                }
+                pending.push(nested);
+            }
+        }
+    }
+
+    false
+}
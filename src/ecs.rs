@@ -0,0 +1,171 @@
+use crate::metadata::{as_keywords, as_u64, attribute, text};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde_json::{Map, Value};
+
+const KEYWORD_AUDIT_FAILURE: u64 = 0x0010_0000_0000_0000;
+const KEYWORD_AUDIT_SUCCESS: u64 = 0x0020_0000_0000_0000;
+
+/// Maps a record (as produced by `into_json_value`) to Elastic Common Schema fields,
+/// following the layout used by Winlogbeat.
+///
+/// Only the most common fields are mapped, fields missing from the record are omitted.
+pub fn to_ecs(record: &Value, timestamp: DateTime<Utc>) -> Value {
+    let event = &record["Event"];
+    let system = &event["System"];
+    let mut ecs = Map::new();
+
+    let event_id = as_u64(text(&system["EventID"]));
+    let provider = attribute(&system["Provider"], "Name");
+    let keywords = as_keywords(&system["Keywords"]);
+
+    insert(
+        &mut ecs,
+        "@timestamp",
+        timestamp
+            .to_rfc3339_opts(SecondsFormat::Micros, true)
+            .into(),
+    );
+
+    insert(&mut ecs, "event.kind", "event".into());
+    insert(
+        &mut ecs,
+        "event.code",
+        event_id.map(|id| id.to_string()).into(),
+    );
+    insert(&mut ecs, "event.provider", provider.clone());
+    insert(&mut ecs, "event.outcome", keywords.and_then(outcome).into());
+    insert(&mut ecs, "host.name", system["Computer"].clone());
+    insert(
+        &mut ecs,
+        "log.level",
+        as_u64(text(&system["Level"])).map(level).into(),
+    );
+
+    insert(&mut ecs, "winlog.channel", system["Channel"].clone());
+    insert(&mut ecs, "winlog.computer_name", system["Computer"].clone());
+    insert(&mut ecs, "winlog.event_id", event_id.into());
+    insert(
+        &mut ecs,
+        "winlog.record_id",
+        system["EventRecordID"].clone(),
+    );
+    insert(&mut ecs, "winlog.provider_name", provider.clone());
+    insert(
+        &mut ecs,
+        "winlog.provider_guid",
+        attribute(&system["Provider"], "Guid").clone(),
+    );
+    insert(&mut ecs, "winlog.version", system["Version"].clone());
+    insert(&mut ecs, "winlog.task", system["Task"].clone());
+    insert(&mut ecs, "winlog.opcode", system["Opcode"].clone());
+    insert(&mut ecs, "winlog.keywords", system["Keywords"].clone());
+
+    let execution = &system["Execution"];
+    insert(
+        &mut ecs,
+        "winlog.process.pid",
+        attribute(execution, "ProcessID").clone(),
+    );
+    insert(
+        &mut ecs,
+        "winlog.process.thread.id",
+        attribute(execution, "ThreadID").clone(),
+    );
+
+    let correlation = &system["Correlation"];
+    insert(
+        &mut ecs,
+        "winlog.activity_id",
+        attribute(correlation, "ActivityID").clone(),
+    );
+    insert(
+        &mut ecs,
+        "winlog.related_activity_id",
+        attribute(correlation, "RelatedActivityID").clone(),
+    );
+
+    insert(
+        &mut ecs,
+        "winlog.user.identifier",
+        attribute(&system["Security"], "UserID").clone(),
+    );
+
+    insert(
+        &mut ecs,
+        "winlog.event_data",
+        data_fields(&event["EventData"]),
+    );
+    insert(
+        &mut ecs,
+        "winlog.user_data",
+        data_fields(&event["UserData"]),
+    );
+
+    Value::Object(ecs)
+}
+
+/// Inserts `value` under a dotted `path`, creating intermediate objects as needed.
+/// Null values are skipped.
+fn insert(object: &mut Map<String, Value>, path: &str, value: Value) {
+    if value.is_null() {
+        return;
+    }
+
+    match path.split_once('.') {
+        Some((key, rest)) => {
+            let child = object
+                .entry(key)
+                .or_insert_with(|| Value::Object(Map::new()));
+
+            if let Value::Object(child) = child {
+                insert(child, rest, value);
+            }
+        }
+        None => {
+            object.insert(path.to_string(), value);
+        }
+    }
+}
+
+/// Returns the named data of an `EventData` or `UserData` element, without its attributes.
+fn data_fields(data: &Value) -> Value {
+    match data {
+        Value::Object(fields) => {
+            let fields: Map<String, Value> = fields
+                .iter()
+                .filter(|(name, _)| name.as_str() != "#attributes")
+                .map(|(name, value)| (name.clone(), value.clone()))
+                .collect();
+
+            if fields.is_empty() {
+                Value::Null
+            } else {
+                Value::Object(fields)
+            }
+        }
+        _ => Value::Null,
+    }
+}
+
+fn outcome(keywords: u64) -> Option<&'static str> {
+    if keywords & KEYWORD_AUDIT_SUCCESS != 0 {
+        Some("success")
+    } else if keywords & KEYWORD_AUDIT_FAILURE != 0 {
+        Some("failure")
+    } else {
+        None
+    }
+}
+
+/// Names of the standard event levels, as rendered by the Windows event viewer.
+fn level(level: u64) -> &'static str {
+    match level {
+        1 => "critical",
+        2 => "error",
+        3 => "warning",
+        5 => "verbose",
+        // `0` is `LogAlways`, which is displayed as information.
+        _ => "information",
+    }
+}
@@ -0,0 +1,374 @@
+use crate::metadata::RecordMetadata;
+use pyo3::exceptions::PyValueError;
+use pyo3::PyErr;
+
+/// A condition on the `System` metadata of records, such as
+/// `event_id in (4624, 4625) and channel == 'Security'`.
+///
+/// Grammar (keywords are case-insensitive, `and` binds tighter than `or`):
+///
+/// ```text
+/// expression := and ("or" and)*
+/// and        := not ("and" not)*
+/// not        := "not" not | "(" expression ")" | comparison
+/// comparison := field ("==" | "!=") literal | field ["not"] "in" "(" literal ("," literal)* ")"
+/// literal    := integer | 'string' | "string"
+/// ```
+#[derive(Debug)]
+pub enum FilterExpression {
+    Or(Box<FilterExpression>, Box<FilterExpression>),
+    And(Box<FilterExpression>, Box<FilterExpression>),
+    Not(Box<FilterExpression>),
+    /// Whether the field has one of the values.
+    In(FieldName, Vec<Literal>),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FieldName {
+    EventId,
+    Version,
+    EventRecordId,
+    Provider,
+    ProviderGuid,
+    Channel,
+}
+
+impl FieldName {
+    const NAMES: &'static str =
+        "event_id, version, event_record_id, provider, provider_guid, channel";
+
+    fn from_name(name: &str) -> Option<FieldName> {
+        match name {
+            "event_id" => Some(FieldName::EventId),
+            "version" => Some(FieldName::Version),
+            "event_record_id" => Some(FieldName::EventRecordId),
+            "provider" => Some(FieldName::Provider),
+            "provider_guid" => Some(FieldName::ProviderGuid),
+            "channel" => Some(FieldName::Channel),
+            _ => None,
+        }
+    }
+
+    fn is_numeric(self) -> bool {
+        matches!(
+            self,
+            FieldName::EventId | FieldName::Version | FieldName::EventRecordId
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Literal {
+    Int(u64),
+    Str(String),
+}
+
+impl FilterExpression {
+    pub fn parse(source: &str) -> Result<FilterExpression, PyErr> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser {
+            tokens: &tokens,
+            position: 0,
+        };
+
+        let expression = parser.expression()?;
+        match parser.peek() {
+            None => Ok(expression),
+            Some(token) => Err(parse_error(
+                token.offset,
+                &format!("unexpected `{}`", token.text),
+            )),
+        }
+    }
+
+    pub fn matches(&self, metadata: &RecordMetadata, event_record_id: u64) -> bool {
+        match self {
+            FilterExpression::Or(a, b) => {
+                a.matches(metadata, event_record_id) || b.matches(metadata, event_record_id)
+            }
+            FilterExpression::And(a, b) => {
+                a.matches(metadata, event_record_id) && b.matches(metadata, event_record_id)
+            }
+            FilterExpression::Not(a) => !a.matches(metadata, event_record_id),
+            FilterExpression::In(field, literals) => {
+                let value = match field {
+                    FieldName::EventId => metadata.event_id.map(Literal::Int),
+                    FieldName::Version => metadata.version.map(Literal::Int),
+                    FieldName::EventRecordId => Some(Literal::Int(event_record_id)),
+                    FieldName::Provider => metadata.provider_name.clone().map(Literal::Str),
+                    FieldName::ProviderGuid => metadata
+                        .provider_guid
+                        .as_deref()
+                        .map(|guid| Literal::Str(normalize_guid(guid))),
+                    FieldName::Channel => metadata.channel.clone().map(Literal::Str),
+                };
+
+                value.is_some_and(|value| literals.contains(&value))
+            }
+        }
+    }
+}
+
+/// GUIDs are compared case-insensitively, with or without braces.
+fn normalize_guid(guid: &str) -> String {
+    guid.trim_start_matches('{')
+        .trim_end_matches('}')
+        .to_lowercase()
+}
+
+fn parse_error(offset: usize, message: &str) -> PyErr {
+    PyErr::new::<PyValueError, _>(format!(
+        "Invalid filter expression at offset {}: {}",
+        offset, message
+    ))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TokenKind {
+    Word,
+    Int(u64),
+    Str(String),
+    Symbol,
+}
+
+#[derive(Debug)]
+struct Token {
+    kind: TokenKind,
+    text: String,
+    offset: usize,
+}
+
+impl Token {
+    fn is_keyword(&self, keyword: &str) -> bool {
+        self.kind == TokenKind::Word && self.text.eq_ignore_ascii_case(keyword)
+    }
+
+    fn is_symbol(&self, symbol: &str) -> bool {
+        self.kind == TokenKind::Symbol && self.text == symbol
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, PyErr> {
+    let mut tokens = vec![];
+    let mut chars = source.char_indices().peekable();
+
+    while let Some(&(offset, c)) = chars.peek() {
+        let (kind, end) = match c {
+            _ if c.is_whitespace() => {
+                chars.next();
+                continue;
+            }
+            '(' | ')' | ',' => {
+                chars.next();
+                (TokenKind::Symbol, offset + 1)
+            }
+            '=' | '!' => {
+                chars.next();
+                match chars.next() {
+                    Some((_, '=')) => (TokenKind::Symbol, offset + 2),
+                    _ => return Err(parse_error(offset, "expected `==` or `!=`")),
+                }
+            }
+            '\'' | '"' => {
+                chars.next();
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some((end, quote)) if quote == c => break (TokenKind::Str(value), end + 1),
+                        Some((_, other)) => value.push(other),
+                        None => return Err(parse_error(offset, "unterminated string")),
+                    }
+                }
+            }
+            _ if c.is_ascii_digit() => {
+                let mut end = offset;
+                while let Some(&(i, digit)) = chars.peek() {
+                    if !digit.is_ascii_alphanumeric() {
+                        break;
+                    }
+                    end = i + digit.len_utf8();
+                    chars.next();
+                }
+
+                let value = source[offset..end]
+                    .parse()
+                    .map_err(|_| parse_error(offset, "invalid integer"))?;
+                (TokenKind::Int(value), end)
+            }
+            _ if c.is_alphabetic() || c == '_' => {
+                let mut end = offset;
+                while let Some(&(i, letter)) = chars.peek() {
+                    if !(letter.is_alphanumeric() || letter == '_') {
+                        break;
+                    }
+                    end = i + letter.len_utf8();
+                    chars.next();
+                }
+                (TokenKind::Word, end)
+            }
+            _ => return Err(parse_error(offset, &format!("unexpected `{}`", c))),
+        };
+
+        tokens.push(Token {
+            kind,
+            text: source[offset..end].to_string(),
+            offset,
+        });
+    }
+
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: &'a [Token],
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&'a Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn advance(&mut self) -> Result<&'a Token, PyErr> {
+        let offset = self.tokens.last().map_or(0, |t| t.offset + t.text.len());
+        let token = self
+            .tokens
+            .get(self.position)
+            .ok_or_else(|| parse_error(offset, "unexpected end of expression"))?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    fn next_if(&mut self, f: impl Fn(&Token) -> bool) -> bool {
+        match self.peek() {
+            Some(token) if f(token) => {
+                self.position += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn expect_symbol(&mut self, symbol: &str) -> Result<(), PyErr> {
+        let token = self.advance()?;
+        if token.is_symbol(symbol) {
+            Ok(())
+        } else {
+            Err(parse_error(
+                token.offset,
+                &format!("expected `{}`, found `{}`", symbol, token.text),
+            ))
+        }
+    }
+
+    fn expression(&mut self) -> Result<FilterExpression, PyErr> {
+        let mut expression = self.conjunction()?;
+        while self.next_if(|t| t.is_keyword("or")) {
+            expression = FilterExpression::Or(Box::new(expression), Box::new(self.conjunction()?));
+        }
+        Ok(expression)
+    }
+
+    fn conjunction(&mut self) -> Result<FilterExpression, PyErr> {
+        let mut expression = self.negation()?;
+        while self.next_if(|t| t.is_keyword("and")) {
+            expression = FilterExpression::And(Box::new(expression), Box::new(self.negation()?));
+        }
+        Ok(expression)
+    }
+
+    fn negation(&mut self) -> Result<FilterExpression, PyErr> {
+        if self.next_if(|t| t.is_keyword("not")) {
+            return Ok(FilterExpression::Not(Box::new(self.negation()?)));
+        }
+
+        if self.next_if(|t| t.is_symbol("(")) {
+            let expression = self.expression()?;
+            self.expect_symbol(")")?;
+            return Ok(expression);
+        }
+
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<FilterExpression, PyErr> {
+        let token = self.advance()?;
+        let field = match token.kind {
+            TokenKind::Word => FieldName::from_name(&token.text),
+            _ => None,
+        }
+        .ok_or_else(|| {
+            parse_error(
+                token.offset,
+                &format!(
+                    "expected a field, found `{}` (possible fields are: {})",
+                    token.text,
+                    FieldName::NAMES
+                ),
+            )
+        })?;
+
+        let operator = self.advance()?;
+        let (negated, literals) = if operator.is_symbol("==") || operator.is_symbol("!=") {
+            (operator.is_symbol("!="), vec![self.literal(field)?])
+        } else {
+            let negated = operator.is_keyword("not");
+            let operator = if negated { self.advance()? } else { operator };
+            if !operator.is_keyword("in") {
+                return Err(parse_error(
+                    operator.offset,
+                    &format!(
+                        "expected `==`, `!=`, `in` or `not in`, found `{}`",
+                        operator.text
+                    ),
+                ));
+            }
+
+            self.expect_symbol("(")?;
+            let mut literals = vec![self.literal(field)?];
+            while self.next_if(|t| t.is_symbol(",")) {
+                literals.push(self.literal(field)?);
+            }
+            self.expect_symbol(")")?;
+
+            (negated, literals)
+        };
+
+        let comparison = FilterExpression::In(field, literals);
+        Ok(if negated {
+            FilterExpression::Not(Box::new(comparison))
+        } else {
+            comparison
+        })
+    }
+
+    fn literal(&mut self, field: FieldName) -> Result<Literal, PyErr> {
+        let token = self.advance()?;
+        match &token.kind {
+            TokenKind::Int(value) if field.is_numeric() => Ok(Literal::Int(*value)),
+            TokenKind::Str(value) if !field.is_numeric() => {
+                Ok(Literal::Str(if field == FieldName::ProviderGuid {
+                    normalize_guid(value)
+                } else {
+                    value.clone()
+                }))
+            }
+            TokenKind::Int(_) | TokenKind::Str(_) => Err(parse_error(
+                token.offset,
+                &format!(
+                    "`{}` is not a valid value for this field, expected {}",
+                    token.text,
+                    if field.is_numeric() {
+                        "an integer"
+                    } else {
+                        "a string"
+                    }
+                ),
+            )),
+            _ => Err(parse_error(
+                token.offset,
+                &format!("expected a value, found `{}`", token.text),
+            )),
+        }
+    }
+}
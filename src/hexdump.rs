@@ -0,0 +1,35 @@
+use std::fmt::Write;
+
+const BYTES_PER_LINE: usize = 16;
+
+/// Formats `data` like `hexdump -C`: an offset, sixteen bytes in hex, and the same bytes as ASCII.
+pub fn hexdump(data: &[u8]) -> String {
+    let mut dump = String::new();
+
+    for (line, bytes) in data.chunks(BYTES_PER_LINE).enumerate() {
+        let _ = write!(dump, "{:08x} ", line * BYTES_PER_LINE);
+
+        for i in 0..BYTES_PER_LINE {
+            if i % 8 == 0 {
+                dump.push(' ');
+            }
+
+            match bytes.get(i) {
+                Some(byte) => {
+                    let _ = write!(dump, "{:02x} ", byte);
+                }
+                None => dump.push_str("   "),
+            }
+        }
+
+        dump.push_str(" |");
+        dump.extend(bytes.iter().map(|&byte| match byte {
+            0x20..=0x7e => byte as char,
+            _ => '.',
+        }));
+        dump.push_str("|\n");
+    }
+
+    let _ = writeln!(dump, "{:08x}", data.len());
+    dump
+}
@@ -0,0 +1,87 @@
+use pyo3::exceptions::PyValueError;
+use pyo3::PyErr;
+use serde_json::{Map, Value};
+
+const ATTRIBUTES_KEY: &str = "#attributes";
+
+/// Controls how XML attributes are laid out in JSON records.
+#[derive(Copy, Clone, Debug, PartialOrd, PartialEq, Eq)]
+pub enum JsonShape {
+    /// The layout produced by the `evtx` crate, attributes are nested under `#attributes`.
+    Raw,
+    /// Attributes are hoisted into their element, prefixed with `@`.
+    Compact,
+    /// Attributes are hoisted into their element without a prefix,
+    /// and elements holding nothing but a single attribute are replaced by its value.
+    Winlogbeat,
+}
+
+impl JsonShape {
+    pub fn from_name(name: &str) -> Result<JsonShape, PyErr> {
+        match name {
+            "raw" => Ok(JsonShape::Raw),
+            "compact" => Ok(JsonShape::Compact),
+            "winlogbeat" => Ok(JsonShape::Winlogbeat),
+            _ => Err(PyErr::new::<PyValueError, _>(format!(
+                "Unknown JSON shape `[{}]`, possible values are: raw, compact, winlogbeat",
+                name
+            ))),
+        }
+    }
+
+    /// Reshapes `value` in place.
+    pub fn apply(self, value: &mut Value) {
+        if self == JsonShape::Raw {
+            return;
+        }
+
+        match value {
+            Value::Object(map) => {
+                for child in map.values_mut() {
+                    self.apply(child);
+                }
+
+                let attributes = match map.shift_remove(ATTRIBUTES_KEY) {
+                    Some(Value::Object(attributes)) => attributes,
+                    Some(other) => {
+                        map.shift_insert(0, ATTRIBUTES_KEY.to_string(), other);
+                        return;
+                    }
+                    None => return,
+                };
+
+                if self == JsonShape::Winlogbeat && map.is_empty() && attributes.len() == 1 {
+                    if let Some((_, attribute)) = attributes.into_iter().next() {
+                        *value = attribute;
+                    }
+                    return;
+                }
+
+                self.hoist_attributes(map, attributes);
+            }
+            Value::Array(values) => {
+                for child in values.iter_mut() {
+                    self.apply(child);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Moves `attributes` to the front of `element`, where `#attributes` used to be.
+    fn hoist_attributes(self, element: &mut Map<String, Value>, attributes: Map<String, Value>) {
+        let mut hoisted = Map::new();
+
+        for (name, attribute) in attributes {
+            let key = match self {
+                JsonShape::Winlogbeat if !element.contains_key(&name) => name,
+                _ => format!("@{}", name),
+            };
+
+            hoisted.insert(key, attribute);
+        }
+
+        hoisted.extend(std::mem::take(element));
+        *element = hoisted;
+    }
+}
@@ -2,30 +2,126 @@
 #![deny(unused_must_use)]
 #![cfg_attr(not(debug_assertions), deny(clippy::dbg_macro))]
 
+use evtx_rs::model::deserialized::{BinXMLDeserializedTokens, BinXmlTemplateRef};
 use evtx_rs::{
     err,
     err::{ChunkError, DeserializationError, EvtxError, InputError, SerializationError},
-    EvtxParser, IntoIterChunks, ParserSettings, SerializedEvtxRecord,
+    EvtxChunkData, EvtxParser, EvtxRecord, ParserSettings, SerializedEvtxRecord,
 };
 
+mod binary_encoding;
+mod checkpoint;
+mod checksums;
+mod chunk_records;
+mod codecs;
+mod columns;
+mod depth;
+mod ecs;
+mod filter_expression;
+mod hexdump;
+mod json_shape;
+mod memory;
+mod metadata;
+mod msgpack;
+mod raw_records;
+mod readers;
+mod sections;
+mod text;
+mod tuples;
+mod values;
+mod walk;
+mod xml_chars;
+
+use binary_encoding::BinaryEncoding;
+use checkpoint::Checkpoint;
+use checksums::ChunkChecksums;
+use chunk_records::ChunkRecords;
+use codecs::ansi_codec_from_name;
+use columns::{column_to_pylist, ColumnType};
+use depth::exceeds_depth;
+use ecs::to_ecs;
+use filter_expression::FilterExpression;
+use hexdump::hexdump;
+use json_shape::JsonShape;
+use memory::MemoryEstimate;
+use metadata::RecordMetadata;
+use msgpack::{encode_map, Field};
+use raw_records::iter_raw_records;
+use readers::{
+    split_concatenated_files, ConcatenatedBuffers, ReadFailure, Rebased, Retrying, Window,
+};
+use sections::SectionFilter;
+use text::{record_has_payload, text_content};
+use tuples::{record_to_pytuple, tuple_fields, TupleField};
+use values::{
+    binary_values, json_to_pyobject, template_guid, template_instances, value_to_pyobject,
+};
+use xml_chars::{contains_invalid_chars, InvalidXmlChars};
+
+use pyo3::types::PyBytes;
 use pyo3::types::PyDict;
+use pyo3::types::PyList;
+use pyo3::types::PySlice;
 use pyo3::types::PyString;
+use pyo3::types::PyTuple;
 
 use pyo3::{
-    exceptions::PyFileNotFoundError, exceptions::PyNotImplementedError, exceptions::PyOSError,
-    exceptions::PyRuntimeError, exceptions::PyValueError, prelude::*,
+    exceptions::PyFileNotFoundError, exceptions::PyKeyError, exceptions::PyNotImplementedError,
+    exceptions::PyOSError, exceptions::PyRuntimeError, exceptions::PyRuntimeWarning,
+    exceptions::PyTypeError, exceptions::PyValueError, prelude::*,
 };
 
-use encoding::all::encodings;
+use chrono::{DateTime, Utc};
+use encoding::EncodingRef;
 use pyo3_file::PyFileLikeObject;
-
+use regex::{Regex, RegexBuilder};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::convert::TryFrom;
 use std::error::Error;
+use std::ffi::CString;
 use std::fs::File;
 use std::io;
-use std::io::{Read, Seek};
-use std::sync::Arc;
+use std::io::{BufWriter, Cursor, Read, Seek, SeekFrom, Write};
+use std::ops::{Range, RangeInclusive};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Instant;
 use std::vec::IntoIter;
 
+const EVTX_FILE_HEADER_SIZE: u64 = 4096;
+/// The size of the fields of the file header, as stored in the header itself.
+const EVTX_FILE_HEADER_FIELDS_SIZE: u32 = 128;
+/// The offset of the header block size in the file header, which is where the first chunk starts.
+const HEADER_BLOCK_SIZE_OFFSET: usize = 40;
+const EVTX_CHUNK_SIZE: u64 = 65536;
+const EVTX_FILE_MAGIC: &[u8; 8] = b"ElfFile\x00";
+const RENDERING_INFO: &str = "RenderingInfo";
+
+/// Magics of archives and containers which EVTX files are commonly shipped in,
+/// none of them longer than `EVTX_FILE_MAGIC`.
+const CONTAINER_MAGICS: &[(&[u8], &str)] = &[
+    (b"PK\x03\x04", "a zip archive"),
+    (b"\x1f\x8b", "a gzip stream"),
+    (b"7z\xbc\xaf\x27\x1c", "a 7z archive"),
+    (b"Rar!\x1a\x07", "a rar archive"),
+    (b"\xfd7zXZ\x00", "an xz stream"),
+    (b"BZh", "a bzip2 stream"),
+    (b"\x28\xb5\x2f\xfd", "a zstd stream"),
+    (
+        b"\xd0\xcf\x11\xe0\xa1\xb1\x1a\xe1",
+        "an OLE compound file (such as a password protected Office document)",
+    ),
+];
+
+/// Whether WEVT template support is compiled in.
+/// The `evtx` version these bindings are built against does not provide it.
+const WEVT_TEMPLATES: bool = false;
+
+const DEFAULT_MAX_DEPTH: usize = 128;
+
 pub trait ReadSeek: Read + Seek + Send + Sync + 'static {
     fn tell(&mut self) -> io::Result<u64> {
         self.stream_position()
@@ -34,6 +130,8 @@ pub trait ReadSeek: Read + Seek + Send + Sync + 'static {
 
 impl<T: Read + Seek + Send + Sync + 'static> ReadSeek for T {}
 
+type BoxedParser = EvtxParser<Box<dyn ReadSeek>>;
+
 struct PyEvtxError(EvtxError);
 
 fn py_err_from_io_err(e: &io::Error) -> PyErr {
@@ -85,42 +183,420 @@ impl From<PyEvtxError> for PyErr {
 
 #[derive(Copy, Clone, PartialOrd, PartialEq, Eq)]
 pub enum OutputFormat {
-    JSON,
+    JSON(JsonShape),
     XML,
+    /// JSON, mapped to Elastic Common Schema fields.
+    ECS,
+}
+
+impl OutputFormat {
+    fn from_name(name: &str) -> PyResult<OutputFormat> {
+        match name {
+            "xml" => Ok(OutputFormat::XML),
+            "json" => Ok(OutputFormat::JSON(JsonShape::Raw)),
+            _ => Err(PyErr::new::<PyValueError, _>(format!(
+                "Unknown output format `[{}]`, possible values are: xml, json",
+                name
+            ))),
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialOrd, PartialEq, Eq)]
+pub enum ChecksumPolicy {
+    Ignore,
+    Warn,
+    Error,
+}
+
+impl ChecksumPolicy {
+    fn name(self) -> &'static str {
+        match self {
+            ChecksumPolicy::Ignore => "ignore",
+            ChecksumPolicy::Warn => "warn",
+            ChecksumPolicy::Error => "error",
+        }
+    }
+
+    fn from_name(name: &str) -> PyResult<ChecksumPolicy> {
+        match name {
+            "ignore" => Ok(ChecksumPolicy::Ignore),
+            "warn" => Ok(ChecksumPolicy::Warn),
+            "error" => Ok(ChecksumPolicy::Error),
+            _ => Err(PyErr::new::<PyValueError, _>(format!(
+                "Unknown checksum policy `[{}]`, possible values are: ignore, warn, error",
+                name
+            ))),
+        }
+    }
+}
+
+fn ansi_codec_setting(ansi_codec: Option<String>) -> PyResult<EncodingRef> {
+    match ansi_codec {
+        Some(codec) => ansi_codec_from_name(&codec).ok_or_else(|| {
+            PyErr::new::<PyValueError, _>(format!(
+                "Unknown encoding `[{}]`, see help for possible values",
+                codec
+            ))
+        }),
+        None => Ok(ParserSettings::default().get_ansi_codec()),
+    }
 }
 
 #[derive(Debug)]
 enum FileOrFileLike {
-    File(String),
-    FileLike(PyFileLikeObject),
+    File(PathBuf),
+    /// A file-like object, along with its `repr()` for errors.
+    FileLike(PyFileLikeObject, String),
+    Bytes(Vec<u8>),
+    Buffers(ConcatenatedBuffers),
 }
 
 impl FileOrFileLike {
     pub fn from_pyobject(path_or_file_like: PyObject) -> PyResult<FileOrFileLike> {
         Python::with_gil(|py| {
             if let Ok(string_ref) = path_or_file_like.downcast_bound::<PyString>(py) {
-                return Ok(FileOrFileLike::File(
+                return Ok(FileOrFileLike::File(PathBuf::from(
                     string_ref.to_string_lossy().to_string(),
-                ));
+                )));
+            }
+
+            if let Ok(bytes) = path_or_file_like.downcast_bound::<PyBytes>(py) {
+                return Ok(FileOrFileLike::Bytes(bytes.as_bytes().to_vec()));
+            }
+
+            let object = path_or_file_like.bind(py);
+            if object.is_instance_of::<PyList>() || object.is_instance_of::<PyTuple>() {
+                let buffers = object.extract().map_err(|_| {
+                    PyErr::new::<PyTypeError, _>(
+                        "A list of buffers to parse must only hold `bytes` objects",
+                    )
+                })?;
+                return Ok(FileOrFileLike::Buffers(ConcatenatedBuffers::new(
+                    py, buffers,
+                )));
+            }
+
+            // `pathlib.Path` and other `os.PathLike` objects.
+            if path_or_file_like.bind(py).hasattr("__fspath__")? {
+                return Ok(FileOrFileLike::File(path_or_file_like.extract(py)?));
             }
 
             // We only need read + seek
-            match PyFileLikeObject::with_requirements(path_or_file_like, true, false, true, true) {
-                Ok(f) => Ok(FileOrFileLike::FileLike(f)),
+            match PyFileLikeObject::with_requirements(
+                path_or_file_like.clone_ref(py),
+                true,
+                false,
+                true,
+                true,
+            ) {
+                Ok(f) => {
+                    ensure_seekable(object)?;
+                    Ok(FileOrFileLike::FileLike(f, object.repr()?.to_string()))
+                }
                 Err(e) => Err(e),
             }
         })
     }
 }
 
+fn validate_pointers(pointers: &[String]) -> PyResult<()> {
+    match pointers
+        .iter()
+        .find(|p| !p.is_empty() && !p.starts_with('/'))
+    {
+        Some(pointer) => Err(PyErr::new::<PyValueError, _>(format!(
+            "Invalid JSON pointer `[{}]`, pointers must be empty or start with `/`",
+            pointer
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Restricts `reader` to `[offset, offset + length)`, after checking that it holds that many bytes.
+fn window_of(mut reader: Box<dyn ReadSeek>, offset: u64, length: u64) -> PyResult<Window> {
+    let len = reader
+        .seek(SeekFrom::End(0))
+        .map_err(|e| py_err_from_io_err(&e))?;
+
+    match offset.checked_add(length) {
+        Some(end) if end <= len => Ok(Window::new(Arc::new(Mutex::new(reader)), offset, end)),
+        _ => Err(PyErr::new::<PyValueError, _>(format!(
+            "Invalid window `[{}, {} + {})`, the input is only {} bytes long",
+            offset, offset, length, len
+        ))),
+    }
+}
+
+/// Fails early with a clear error for empty, truncated or non-EVTX input,
+/// which would otherwise surface as a low-level parsing error.
+///
+/// Returns the offset of the first chunk, as given by the header block size.
+fn ensure_evtx_file(reader: &mut Box<dyn ReadSeek>) -> PyResult<u64> {
+    // The magic, followed by the fields checked for plausibility.
+    const CHECKED_HEADER_SIZE: u64 = HEADER_BLOCK_SIZE_OFFSET as u64 + 2;
+    let mut header = Vec::with_capacity(CHECKED_HEADER_SIZE as usize);
+
+    let len = (|| {
+        let len = reader.seek(SeekFrom::End(0))?;
+        reader.seek(SeekFrom::Start(0))?;
+        reader.take(CHECKED_HEADER_SIZE).read_to_end(&mut header)?;
+        reader.seek(SeekFrom::Start(0))?;
+
+        Ok(len)
+    })()
+    .map_err(|e| py_err_from_io_err(&e))?;
+
+    let magic = &header[..header.len().min(EVTX_FILE_MAGIC.len())];
+
+    if let Some((_, container)) = CONTAINER_MAGICS
+        .iter()
+        .find(|(container_magic, _)| magic.starts_with(container_magic))
+    {
+        return Err(PyErr::new::<PyValueError, _>(format!(
+            "not a valid EVTX file: the input looks like {}, \
+             decompress or extract the EVTX file from it first",
+            container
+        )));
+    }
+
+    let byte_swapped_magics = [
+        swap_bytes(EVTX_FILE_MAGIC, 2),
+        swap_bytes(EVTX_FILE_MAGIC, 4),
+    ];
+    if byte_swapped_magics.iter().any(|swapped| swapped == magic) {
+        return Err(PyErr::new::<PyValueError, _>(
+            "not a valid EVTX file: the input appears to be byte-swapped \
+             (EVTX files are little-endian), it was likely copied with the wrong byte order",
+        ));
+    }
+
+    if len < EVTX_FILE_HEADER_SIZE || magic != EVTX_FILE_MAGIC {
+        return Err(PyErr::new::<PyValueError, _>(format!(
+            "not a valid EVTX file: expected at least {} bytes starting with {:02x?}, \
+             found {} bytes starting with {:02x?}",
+            EVTX_FILE_HEADER_SIZE, EVTX_FILE_MAGIC, len, magic
+        )));
+    }
+
+    // The header size is fixed in every version of the format, and the header block
+    // (4096 bytes, except for some third-party writers) must hold the header,
+    // so anything else means a corrupted or foreign file which would only produce garbage records.
+    let header_size = u32::from_le_bytes([header[32], header[33], header[34], header[35]]);
+    let header_block_size = u16::from_le_bytes([
+        header[HEADER_BLOCK_SIZE_OFFSET],
+        header[HEADER_BLOCK_SIZE_OFFSET + 1],
+    ]);
+    if header_size != EVTX_FILE_HEADER_FIELDS_SIZE
+        || u32::from(header_block_size) < EVTX_FILE_HEADER_FIELDS_SIZE
+    {
+        let byte_swapped = header_size.swap_bytes() == EVTX_FILE_HEADER_FIELDS_SIZE
+            || u64::from(header_block_size.swap_bytes()) == EVTX_FILE_HEADER_SIZE;
+
+        return Err(PyErr::new::<PyValueError, _>(format!(
+            "not a valid EVTX file: implausible file header \
+             (header size {}, expected {}; header block size {}, expected at least {}){}",
+            header_size,
+            EVTX_FILE_HEADER_FIELDS_SIZE,
+            header_block_size,
+            EVTX_FILE_HEADER_FIELDS_SIZE,
+            if byte_swapped {
+                ", the header appears to be byte-swapped (EVTX files are little-endian)"
+            } else {
+                ""
+            }
+        )));
+    }
+
+    Ok(u64::from(header_block_size))
+}
+
+/// Converts an error of `evtx` which may have been caused by a read which failed for good,
+/// to raise the original error rather than the one `evtx` turned it into.
+fn read_error(read_failure: &ReadFailure, error: EvtxError) -> PyErr {
+    match read_failure.take() {
+        Some(failure) => PyErr::new::<PyOSError, _>(failure),
+        None => PyEvtxError(error).into(),
+    }
+}
+
+/// Reverses the byte order of each `width` bytes of `data`.
+fn swap_bytes(data: &[u8], width: usize) -> Vec<u8> {
+    data.chunks(width)
+        .flat_map(|word| word.iter().rev().copied())
+        .collect()
+}
+
+/// Fails early with a clear error for streams which have a `seek` method but can't actually seek
+/// (such as pipes), which would otherwise fail somewhere inside the parser.
+fn ensure_seekable(file_like: &Bound<'_, PyAny>) -> PyResult<()> {
+    // Objects without `seekable()` are given the benefit of the doubt, `tell()` is probed either way.
+    let seekable = match file_like.call_method0("seekable") {
+        Ok(seekable) => seekable.is_truthy()?,
+        Err(_) => true,
+    };
+
+    if seekable && file_like.call_method0("tell").is_ok() {
+        return Ok(());
+    }
+
+    Err(PyErr::new::<PyTypeError, _>(format!(
+        "Parsing EVTX requires a seekable stream, but {} is not seekable",
+        file_like.repr()?
+    )))
+}
+
+// The module is needed for pickling to find the class.
+#[pyclass(module = "evtx")]
+/// PyParserSettings(self, num_threads=0, ansi_codec='windows-1252', validate_checksums=False, separate_json_attributes=False, indent=True, /)
+/// --
+///
+/// Settings of the underlying `evtx` parser, which can be built once and passed
+/// to any number of `PyEvtxParser` instances with `settings=`.
+///
+/// Like the `ParserSettings` builder of `evtx`, every method returns new settings
+/// with a single setting changed, so settings can be built from the defaults in a chain:
+/// `PyParserSettings().num_threads(4).ansi_codec('windows-1251')`.
+///
+/// Settings can be pickled, to be sent to worker processes.
+///
+/// Args:
+///     `num_threads` (int, optional): as `number_of_threads` of `PyEvtxParser`.
+///
+///     `ansi_codec` (str, optional): as in `PyEvtxParser`.
+///
+///     `validate_checksums` (bool, optional): as `checksum_policy='error'` of `PyEvtxParser`.
+///
+///     `separate_json_attributes` (bool, optional): put the attributes of JSON elements
+///            in a sibling `<element>_attributes` object, rather than under `#attributes`.
+///
+///     `indent` (bool, optional): indent JSON records.
+#[derive(Clone)]
+pub struct PyParserSettings {
+    settings: ParserSettings,
+}
+
+#[pymethods]
+impl PyParserSettings {
+    #[new]
+    #[pyo3(signature = (num_threads=0, ansi_codec=None, validate_checksums=false, separate_json_attributes=false, indent=true))]
+    fn new(
+        num_threads: usize,
+        ansi_codec: Option<String>,
+        validate_checksums: bool,
+        separate_json_attributes: bool,
+        indent: bool,
+    ) -> PyResult<Self> {
+        let settings = ParserSettings::new()
+            .num_threads(num_threads)
+            .ansi_codec(ansi_codec_setting(ansi_codec)?)
+            .validate_checksums(validate_checksums)
+            .separate_json_attributes(separate_json_attributes)
+            .indent(indent);
+
+        Ok(PyParserSettings { settings })
+    }
+
+    /// num_threads(self, num_threads, /)
+    /// --
+    ///
+    /// Returns a copy of the settings with `num_threads` changed.
+    fn num_threads(&self, num_threads: usize) -> Self {
+        self.with(|settings| settings.num_threads(num_threads))
+    }
+
+    /// ansi_codec(self, ansi_codec, /)
+    /// --
+    ///
+    /// Returns a copy of the settings with `ansi_codec` changed.
+    fn ansi_codec(&self, ansi_codec: String) -> PyResult<Self> {
+        let codec = ansi_codec_setting(Some(ansi_codec))?;
+        Ok(self.with(|settings| settings.ansi_codec(codec)))
+    }
+
+    /// validate_checksums(self, validate_checksums, /)
+    /// --
+    ///
+    /// Returns a copy of the settings with `validate_checksums` changed.
+    fn validate_checksums(&self, validate_checksums: bool) -> Self {
+        self.with(|settings| settings.validate_checksums(validate_checksums))
+    }
+
+    /// separate_json_attributes(self, separate_json_attributes, /)
+    /// --
+    ///
+    /// Returns a copy of the settings with `separate_json_attributes` changed.
+    fn separate_json_attributes(&self, separate_json_attributes: bool) -> Self {
+        self.with(|settings| settings.separate_json_attributes(separate_json_attributes))
+    }
+
+    /// indent(self, indent, /)
+    /// --
+    ///
+    /// Returns a copy of the settings with `indent` changed.
+    fn indent(&self, indent: bool) -> Self {
+        self.with(|settings| settings.indent(indent))
+    }
+
+    fn __reduce__<'py>(slf: &Bound<'py, Self>) -> PyResult<(Bound<'py, PyAny>, PyObject)> {
+        let py = slf.py();
+        let settings = &slf.borrow().settings;
+        let args = (
+            *settings.get_num_threads(),
+            settings.get_ansi_codec().name(),
+            settings.should_validate_checksums(),
+            settings.should_separate_json_attributes(),
+            settings.should_indent(),
+        );
+
+        Ok((
+            slf.get_type().into_any(),
+            args.into_pyobject(py)?.into_any().unbind(),
+        ))
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "PyParserSettings(num_threads={}, ansi_codec='{}', validate_checksums={}, \
+             separate_json_attributes={}, indent={})",
+            self.settings.get_num_threads(),
+            self.settings.get_ansi_codec().name(),
+            py_bool(self.settings.should_validate_checksums()),
+            py_bool(self.settings.should_separate_json_attributes()),
+            py_bool(self.settings.should_indent()),
+        )
+    }
+}
+
+impl PyParserSettings {
+    fn with(&self, f: impl FnOnce(ParserSettings) -> ParserSettings) -> Self {
+        PyParserSettings {
+            settings: f(self.settings.clone()),
+        }
+    }
+}
+
+/// Formats a `bool` the way Python does.
+fn py_bool(value: bool) -> &'static str {
+    if value {
+        "True"
+    } else {
+        "False"
+    }
+}
+
 #[pyclass]
-/// PyEvtxParser(self, path_or_file_like, number_of_threads=0, ansi_codec='windows-1252', /)
+/// PyEvtxParser(self, path_or_file_like, number_of_threads=0, ansi_codec='windows-1252', byte_range=None, bare=False, checksum_policy='ignore', multi_file=False, extract_binary=False, report_record_errors=False, max_depth=128, include_metadata=False, max_records=None, timestamp_suffix=True, ansi_codec_fallbacks=None, xml_invalid_chars='keep', binary_encoding='hex', on_chunk_error=None, content_hash=False, skip_empty=False, rendering_info=True, window=None, settings=None, read_retries=0, /)
 /// --
 ///
 /// Returns an instance of the parser.
 ///
 /// Args:
-///     `path_or_file_like`: a path (string), or a file-like object.
+///     `path_or_file_like`: a path (`str` or `os.PathLike`), the contents of a file (`bytes`),
+///            or a file-like object.
+///            The contents of a file can also be given as a list of `bytes` objects
+///            (such as pieces fetched separately), which are parsed as if they were
+///            concatenated, without copying them into a single buffer.
 ///
 ///     `number_of_threads` (int, optional):
 ///            limit the number of worker threads used by rust.
@@ -139,201 +615,2927 @@ impl FileOrFileLike {
 ///                      windows-949, euc-jp, windows-31j, gbk, gb18030, hz, big5-2003,
 ///                      pua-mapped-binary, iso-8859-8-i
 ///
+///                  Python codec names and common aliases (such as `cp1252` or `latin-1`)
+///                  are accepted as well.
+///
+///     `byte_range` (tuple[int, int], optional):
+///            a `(start, end)` pair of file offsets, limiting the parser to the chunks
+///            starting inside `[start, end)`.
+///            Adjacent ranges never share a chunk, so a file can be split between workers
+///            by byte offset.
+///
+///     `bare` (bool, optional):
+///            strip the XML declaration and any surrounding whitespace from each record,
+///            so records can be concatenated into a single document.
+///
+///     `checksum_policy` (str, optional): what to do with chunks whose checksums do not match.
+///
+///            `ignore` (the default) parses them without validating their checksums.
+///            `warn` parses them anyway, but emits a `RuntimeWarning` and records
+///            the chunk number in the iterator's `checksum_mismatches`.
+///            `error` raises a `RuntimeError` for them.
+///
+///     `multi_file` (bool, optional):
+///            treat the input as several EVTX files stored back to back (e.g. carved evidence),
+///            continuing with the next file whenever a file header is found
+///            where a chunk was expected.
+///            Cannot be combined with `byte_range`.
+///
+///     `extract_binary` (bool, optional):
+///            add a `binary_data` key to each record dict, holding the contents of
+///            the record's binary values as a list of `bytes` (in document order),
+///            rather than only the hex strings found in `data`.
+///
+///     `report_record_errors` (bool, optional):
+///            records which fail to deserialize are skipped by default.
+///            When set, a dict with `chunk_number`, `chunk_offset` (in the file),
+///            `record_index` (within the chunk), `previous_record_id` (the id of the last record
+///            which was deserialized before it, or `None`) and `error` keys
+///            is yielded in place of each such record.
+///            Either way, the number of failed records is available
+///            as the `record_errors` attribute of the iterator.
+///
+///     `max_depth` (int, optional):
+///            the maximum nesting depth of a record, counting elements, templates and nested
+///            BinXml values. Deeper records are not rendered, and are treated like records which
+///            failed to deserialize (see `report_record_errors`). This guards against crafted files
+///            exhausting the stack. Records expanding to more than a million tokens, which only
+///            crafted templates do, are rejected as well. `None` disables the check.
+///
+///     `include_metadata` (bool, optional):
+///            add the following keys to each record dict:
+///            `raw_timestamp`, the record's timestamp as the original FILETIME integer
+///            (100 nanosecond intervals since 1601-01-01 UTC), for exact comparisons
+///            which don't go through a string or a `datetime`.
+///            `size`, the size of the record in the file, in bytes.
+///            `data_size`, the size of the serialized `data`, in bytes.
+///            `ansi_codec`, the codec the record's ANSI strings were decoded with.
+///            `chunk_number` and `record_index`, the number of the record's chunk and its
+///            0-based position within the chunk, which locate it even when record ids
+///            are duplicated or zeroed (as in corrupted files).
+///            `activity_id` and `related_activity_id`, the GUIDs of the `Correlation` element,
+///            which link related events (such as the events of a logon session),
+///            or `None` when the record has none.
+///
+///     `max_records` (int, optional):
+///            a safety cap for untrusted input. Iterating raises a `RuntimeError`
+///            as soon as more than this many records have been parsed (whether yielded or not),
+///            with the cap and the number of records parsed as its 2nd and 3rd `args`.
+///            Unlike slicing the iterator, which stops cleanly, this signals a file
+///            with an unexpected number of records.
+///
+///     `timestamp_suffix` (bool, optional):
+///            whether the `timestamp` of record dicts ends with ` UTC` (the default).
+///            When unset, timestamps look like `2016-06-29 15:24:34.346`,
+///            which strict ISO 8601 parsers (such as `datetime.fromisoformat`) accept.
+///
+///     `ansi_codec_fallbacks` (list[str], optional):
+///            codecs to retry records with, in order, when their ANSI strings
+///            fail to decode with `ansi_codec`, or decode to replacement characters (`\ufffd`),
+///            for collections mixing encodings. A record which no codec decodes without
+///            replacement characters is decoded with the first codec which doesn't fail.
+///            With `include_metadata`, the codec which was used is given
+///            by the `ansi_codec` key of each record dict.
+///
+///     `xml_invalid_chars` (str, optional): what to do with characters which are not allowed
+///            in XML 1.0 (such as most control characters), when serializing records to XML.
+///
+///            `keep` (the default) leaves them in place, producing records XML parsers may reject.
+///            `replace` replaces them with U+FFFD.
+///            `escape` replaces them with `_xHHHH_` escapes (e.g. `_x0001_` for U+0001),
+///            as .NET's `XmlConvert` and Office documents do. In the text and attribute values
+///            holding such characters, the `_` of text which reads like an escape is escaped
+///            as `_x005F_`, while names and other values are left as is. Character references
+///            (`&#x1;`) would not do, as they are only well-formed in XML 1.1.
+///            `error` treats records holding them like records which failed to deserialize
+///            (see `report_record_errors`).
+///
+///            Strings which are not valid UTF-16 can't be decoded at all, the records holding them
+///            always fail to deserialize.
+///
+///     `binary_encoding` (str, optional): how binary values are rendered in XML and JSON records.
+///
+///            `hex` (the default) renders them as upper case hex digits.
+///            `base64` renders them as base64, which is half as large for records carrying blobs.
+///            `cdata` renders them as hex digits in a CDATA section (`<![CDATA[0A1B]]>`) in XML
+///            records, which sets them apart from strings, and as plain hex digits in JSON ones.
+///
+///            Either way the output is well-formed, and the raw bytes are available
+///            through `extract_binary`.
+///
+///     `on_chunk_error` (callable, optional): called as `on_chunk_error(chunk_id, error_message)`
+///            when a chunk fails to parse (including checksum mismatches with
+///            `checksum_policy='error'`), instead of raising. Returning `True` skips the chunk
+///            and moves on to the next one, returning `False` stops the iteration.
+///            Exceptions raised by the callable propagate to the caller.
+///
+///     `content_hash` (bool, optional):
+///            add a `content_hash` key to each record dict, holding the SHA-256 of the raw
+///            record (header included) as hex. It doesn't depend on any output setting,
+///            so it identifies duplicate records across collections and exports, and shows
+///            whether a record was altered between runs.
+///
+///     `skip_empty` (bool, optional):
+///            skip the records without a payload, that is, whose `EventData` and `UserData`
+///            sections are both missing, or hold no value other than empty or whitespace-only
+///            strings (element names and attributes don't count, `0` does).
+///            Records are checked before being serialized, and skipped records are not yielded
+///            by any of the iterators.
+///
+///     `rendering_info` (bool, optional):
+///            whether to keep the `RenderingInfo` section of records (the default), which
+///            forwarded events carry with messages pre-rendered on the source machine.
+///            It can be large and is specific to the source's locale, so pipelines doing
+///            their own rendering may want to leave it out of XML and JSON records.
+///
+///     `window` (tuple[int, int], optional):
+///            an `(offset, length)` pair, to parse the file stored at `[offset, offset + length)`
+///            of the input (such as a file carved out of a disk image) without extracting it first.
+///            Everything else treats the window as the whole file: `byte_range`,
+///            the `chunk_offset` of errors and the other offsets are relative to its start.
+///            Raises `ValueError` if the window extends past the end of the input.
+///
+///     `settings` (PyParserSettings, optional):
+///            settings of the underlying `evtx` parser, built once and shared between parsers.
+///            `number_of_threads`, `ansi_codec` and `checksum_policy` override the corresponding
+///            settings when they are given.
+///
+///     `read_retries` (int, optional):
+///            for file-like objects, the number of times a failed `read()` or `seek()`
+///            is retried (waiting 0.1s, then twice as long before every following retry)
+///            before giving up, for streams over a flaky connection (such as cloud storage).
+///            Errors raised once retries are exhausted name the file-like object.
+///
 pub struct PyEvtxParser {
     inner: Option<EvtxParser<Box<dyn ReadSeek>>>,
+    /// Parsers for the files following the first one, when `multi_file` is set.
+    following: Vec<EvtxParser<Box<dyn ReadSeek>>>,
     configuration: ParserSettings,
+    chunks: Range<u64>,
+    bare: bool,
+    checksum_policy: ChecksumPolicy,
+    extract_binary: bool,
+    report_record_errors: bool,
+    max_depth: Option<usize>,
+    include_metadata: bool,
+    max_records: Option<u64>,
+    timestamp_suffix: bool,
+    ansi_codec_fallbacks: Vec<EncodingRef>,
+    xml_invalid_chars: InvalidXmlChars,
+    binary_encoding: BinaryEncoding,
+    on_chunk_error: Option<PyObject>,
+    content_hash: bool,
+    skip_empty: bool,
+    rendering_info: bool,
+    /// The size of the input, when it is held in memory.
+    buffered_input: u64,
+    /// Where the chunks start, which is 4096 except for some third-party writers.
+    first_chunk_offset: u64,
+    read_retries: u32,
+    read_failure: ReadFailure,
+    /// The number of records skipped by the last scan, see `record_errors()`.
+    record_errors: u64,
 }
 
 #[pymethods]
 impl PyEvtxParser {
     #[new]
-    #[pyo3(signature = (path_or_file_like, number_of_threads=None, ansi_codec=None))]
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (path_or_file_like, number_of_threads=None, ansi_codec=None, byte_range=None, bare=false, checksum_policy=None, multi_file=false, extract_binary=false, report_record_errors=false, max_depth=Some(DEFAULT_MAX_DEPTH), include_metadata=false, max_records=None, timestamp_suffix=true, ansi_codec_fallbacks=None, xml_invalid_chars=None, binary_encoding=None, on_chunk_error=None, content_hash=false, skip_empty=false, rendering_info=true, window=None, settings=None, read_retries=0))]
     fn new(
         path_or_file_like: PyObject,
         number_of_threads: Option<usize>,
         ansi_codec: Option<String>,
+        byte_range: Option<(u64, u64)>,
+        bare: bool,
+        checksum_policy: Option<&str>,
+        multi_file: bool,
+        extract_binary: bool,
+        report_record_errors: bool,
+        max_depth: Option<usize>,
+        include_metadata: bool,
+        max_records: Option<u64>,
+        timestamp_suffix: bool,
+        ansi_codec_fallbacks: Option<Vec<String>>,
+        xml_invalid_chars: Option<&str>,
+        binary_encoding: Option<&str>,
+        on_chunk_error: Option<PyObject>,
+        content_hash: bool,
+        skip_empty: bool,
+        rendering_info: bool,
+        window: Option<(u64, u64)>,
+        settings: Option<PyParserSettings>,
+        read_retries: u32,
     ) -> PyResult<Self> {
         let file_or_file_like = FileOrFileLike::from_pyobject(path_or_file_like)?;
+        let buffered_input = match &file_or_file_like {
+            FileOrFileLike::Bytes(b) => b.len() as u64,
+            _ => 0,
+        };
+
+        // Setup `settings`
+        let settings = settings.map(|s| s.settings).unwrap_or_default();
 
         // Setup `ansi_codec`
-        let codec = if let Some(codec) = ansi_codec {
-            match encodings().iter().find(|c| c.name() == codec) {
-                Some(encoding) => *encoding,
-                None => {
-                    return Err(PyErr::new::<PyValueError, _>(format!(
-                        "Unknown encoding `[{}]`, see help for possible values",
-                        codec
-                    )));
-                }
-            }
-        } else {
-            ParserSettings::default().get_ansi_codec()
+        let codec = match ansi_codec {
+            Some(_) => ansi_codec_setting(ansi_codec)?,
+            None => settings.get_ansi_codec(),
         };
+        let ansi_codec_fallbacks = ansi_codec_fallbacks
+            .unwrap_or_default()
+            .into_iter()
+            .map(|codec| ansi_codec_setting(Some(codec)))
+            .collect::<PyResult<Vec<_>>>()?;
 
         // Setup `number_of_threads`
         let number_of_threads = match number_of_threads {
             Some(number) => number,
-            None => *ParserSettings::default().get_num_threads(),
+            None => *settings.get_num_threads(),
+        };
+
+        // Setup `xml_invalid_chars`
+        let xml_invalid_chars = match xml_invalid_chars {
+            Some(mode) => InvalidXmlChars::from_name(mode)?,
+            None => InvalidXmlChars::Keep,
+        };
+
+        // Setup `binary_encoding`
+        let binary_encoding = match binary_encoding {
+            Some(encoding) => BinaryEncoding::from_name(encoding)?,
+            None => BinaryEncoding::Hex,
+        };
+
+        // Setup `on_chunk_error`
+        if let Some(callback) = &on_chunk_error {
+            Python::with_gil(|py| -> PyResult<()> {
+                let callback = callback.bind(py);
+                if !callback.is_callable() {
+                    return Err(PyErr::new::<PyTypeError, _>(format!(
+                        "`on_chunk_error` must be callable, got {}",
+                        callback.repr()?
+                    )));
+                }
+                Ok(())
+            })?;
+        }
+
+        // Setup `byte_range`
+        let byte_range = match byte_range {
+            Some(_) if multi_file => {
+                return Err(PyErr::new::<PyValueError, _>(
+                    "`byte_range` cannot be combined with `multi_file`",
+                ));
+            }
+            Some((start, end)) if start > end => {
+                return Err(PyErr::new::<PyValueError, _>(format!(
+                    "Invalid byte range `[{}, {})`, start must not be greater than end",
+                    start, end
+                )));
+            }
+            byte_range => byte_range,
         };
 
-        let configuration = ParserSettings::new()
+        // Setup `checksum_policy`
+        let checksum_policy = match checksum_policy {
+            Some(policy) => ChecksumPolicy::from_name(policy)?,
+            None if settings.should_validate_checksums() => ChecksumPolicy::Error,
+            None => ChecksumPolicy::Ignore,
+        };
+
+        let configuration = settings
             .ansi_codec(codec)
-            .num_threads(number_of_threads);
+            .num_threads(number_of_threads)
+            .validate_checksums(checksum_policy == ChecksumPolicy::Error);
 
-        let boxed_read_seek = match file_or_file_like {
+        let read_failure = ReadFailure::default();
+        let mut boxed_read_seek = match file_or_file_like {
             FileOrFileLike::File(s) => {
                 let file = File::open(s)?;
                 Box::new(file) as Box<dyn ReadSeek>
             }
-            FileOrFileLike::FileLike(f) => Box::new(f) as Box<dyn ReadSeek>,
+            FileOrFileLike::FileLike(f, source) => {
+                let retrying = Retrying::new(f, source, read_retries, read_failure.clone());
+                Box::new(retrying) as Box<dyn ReadSeek>
+            }
+            FileOrFileLike::Bytes(b) => Box::new(Cursor::new(b)) as Box<dyn ReadSeek>,
+            FileOrFileLike::Buffers(b) => Box::new(b) as Box<dyn ReadSeek>,
+        };
+
+        if let Some((offset, length)) = window {
+            boxed_read_seek = Box::new(window_of(boxed_read_seek, offset, length)?);
+        }
+
+        let first_chunk_offset = ensure_evtx_file(&mut boxed_read_seek)?;
+        if first_chunk_offset != EVTX_FILE_HEADER_SIZE {
+            if multi_file {
+                return Err(PyErr::new::<PyValueError, _>(format!(
+                    "`multi_file` requires files whose first chunk is at offset {}, found {}",
+                    EVTX_FILE_HEADER_SIZE, first_chunk_offset
+                )));
+            }
+
+            boxed_read_seek = Box::new(
+                Rebased::new(boxed_read_seek, first_chunk_offset)
+                    .map_err(|e| py_err_from_io_err(&e))?,
+            );
+        }
+
+        let chunks = match byte_range {
+            Some((start, end)) => {
+                chunk_number_at(start, first_chunk_offset)..chunk_number_at(end, first_chunk_offset)
+            }
+            None => 0..u64::MAX,
         };
 
-        let parser = EvtxParser::from_read_seek(boxed_read_seek)
-            .map_err(PyEvtxError)?
-            .with_configuration(configuration.clone());
+        let read_seeks = if multi_file {
+            split_concatenated_files(boxed_read_seek)?
+                .into_iter()
+                .map(|window| Box::new(window) as Box<dyn ReadSeek>)
+                .collect()
+        } else {
+            vec![boxed_read_seek]
+        };
+
+        let mut parsers = Vec::with_capacity(read_seeks.len());
+        for read_seek in read_seeks {
+            let parser = EvtxParser::from_read_seek(read_seek)
+                .map_err(|e| read_error(&read_failure, e))?
+                .with_configuration(configuration.clone());
+
+            parsers.push(parser);
+        }
+        let parser = parsers.remove(0);
 
         Ok(PyEvtxParser {
             inner: Some(parser),
+            following: parsers,
             configuration,
+            chunks,
+            bare,
+            checksum_policy,
+            extract_binary,
+            report_record_errors,
+            max_depth,
+            include_metadata,
+            max_records,
+            timestamp_suffix,
+            ansi_codec_fallbacks,
+            xml_invalid_chars,
+            binary_encoding,
+            on_chunk_error,
+            content_hash,
+            skip_empty,
+            rendering_info,
+            buffered_input,
+            first_chunk_offset,
+            read_retries,
+            read_failure,
+            record_errors: 0,
         })
     }
 
-    /// records(self, /)
+    /// records(self, providers=None, resume_from=None, transform=None, where=None, keywords_mask=None, /)
     /// --
     ///
     /// Returns an iterator that yields either an XML record, or a `RuntimeError` object.
     ///
+    /// Args:
+    ///     `providers` (list[str], optional): yield only the records of these providers,
+    ///            given by name or GUID (case-insensitive, with or without braces).
+    ///            Records are matched on their `<System>` fields before being rendered,
+    ///            so the ones filtered out cost next to nothing.
+    ///
+    ///     `resume_from` (bytes, optional): a token returned by the `checkpoint()` method
+    ///            of a previous iterator over the same file, to yield only the records
+    ///            following its position. Raises `ValueError` if the token was taken
+    ///            on another file (or the file has since been rewritten).
+    ///
+    ///     `transform` (callable, optional): called with the `data` of each record
+    ///            (the XML string), whatever it returns replaces `data` in the yielded dict.
+    ///            Exceptions raised by the callable propagate out of the iteration.
+    ///
+    ///     `where` (str, optional): yield only the records matching this expression,
+    ///            such as `event_id in (4624, 4625) and channel == 'Security'`.
+    ///            Records are matched against their `System` metadata, before being serialized.
+    ///
+    ///            Fields: `event_id`, `version` and `event_record_id` (integers),
+    ///            `provider` (the provider name), `provider_guid` (compared case-insensitively,
+    ///            with or without braces) and `channel` (strings, in single or double quotes).
+    ///            Operators: `==`, `!=`, `in (...)`, `not in (...)`, `and`, `or`, `not`,
+    ///            and parentheses. A missing field is equal to no value.
+    ///            Raises `ValueError` for malformed expressions.
+    ///
+    ///     `keywords_mask` (int, optional): yield only the records whose `Keywords` bitmask
+    ///            shares at least one bit with this mask, such as `0x0010000000000000`
+    ///            (Audit Failure) or `0x0020000000000000` (Audit Success).
+    ///            Records without keywords never match. Like `where`, this is matched
+    ///            against the `System` metadata, before records are serialized.
+    ///
     /// Note - Iterating over records can raise a `RuntimeError` if the parser encounters an invalid record.
     ///        If using a regular for-loop, this could abruptly terminate the iteration.
     ///
     ///        It is recommended to wrap this iterator with a logic that will continue iteration
     ///        in case an exception object is returned.
-    fn records(&mut self) -> PyResult<PyRecordsIterator> {
-        self.records_iterator(OutputFormat::XML)
+    #[pyo3(signature = (providers=None, resume_from=None, transform=None, r#where=None, keywords_mask=None))]
+    fn records(
+        &mut self,
+        providers: Option<Vec<String>>,
+        resume_from: Option<&[u8]>,
+        transform: Option<PyObject>,
+        r#where: Option<&str>,
+        keywords_mask: Option<u64>,
+    ) -> PyResult<PyRecordsIterator> {
+        let mut iterator = self.records_iterator(OutputFormat::XML)?;
+        iterator.set_filters(providers, r#where, keywords_mask)?;
+        iterator.transform = transform;
+
+        if let Some(token) = resume_from {
+            iterator.resume_from(parse_checkpoint(token)?)?;
+        }
+
+        Ok(iterator)
     }
 
-    /// records_json(self, /)
+    /// records_json(self, shape='raw', resume_from=None, include_fields=None, exclude_fields=None, providers=None, where=None, keywords_mask=None, /)
     /// --
     ///
     /// Returns an iterator that yields either a JSON record, or a `RuntimeError` object.
     ///
+    /// Args:
+    ///     `shape` (str, optional): controls how XML attributes are represented.
+    ///
+    ///            `raw` (the default) keeps attributes nested under an `#attributes` key.
+    ///            `compact` merges attributes into their element, prefixed with `@`.
+    ///            `winlogbeat` merges attributes into their element without a prefix,
+    ///            and replaces elements holding a single attribute by its value
+    ///            (e.g. `TimeCreated` becomes the `SystemTime` string).
+    ///
+    ///     `resume_from` (bytes, optional): a checkpoint to resume from, as in `records()`.
+    ///
+    ///     `include_fields` (list[str], optional): keep only these sections of each record
+    ///            (children of `Event`, such as `System` or `EventData`).
+    ///
+    ///     `exclude_fields` (list[str], optional): drop these sections of each record.
+    ///
+    ///            Sections are pruned before records are serialized, which is much cheaper
+    ///            than dropping them from every record in Python.
+    ///
+    ///     `providers` (list[str], optional): yield only the records of these providers,
+    ///            as in `records()`.
+    ///
+    ///     `where` (str, optional): yield only the records matching this expression,
+    ///            as in `records()`.
+    ///
+    ///     `keywords_mask` (int, optional): yield only the records whose `Keywords` bitmask
+    ///            shares at least one bit with this mask, as in `records()`.
+    ///
+    /// Object keys are always in the order of the source elements and attributes,
+    /// as these bindings build serde_json with its order-preserving map.
+    ///
     /// Note - Iterating over records can raise a `RuntimeError` if the parser encounters an invalid record.
     ///        If using a regular for-loop, this could abruptly terminate the iteration.
     ///
     ///        It is recommended to wrap this iterator with a logic that will continue iteration
     ///        in case an exception object is returned.
-    fn records_json(&mut self) -> PyResult<PyRecordsIterator> {
-        self.records_iterator(OutputFormat::JSON)
-    }
+    #[pyo3(signature = (shape=None, resume_from=None, include_fields=None, exclude_fields=None, providers=None, r#where=None, keywords_mask=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn records_json(
+        &mut self,
+        shape: Option<&str>,
+        resume_from: Option<&[u8]>,
+        include_fields: Option<Vec<String>>,
+        exclude_fields: Option<Vec<String>>,
+        providers: Option<Vec<String>>,
+        r#where: Option<&str>,
+        keywords_mask: Option<u64>,
+    ) -> PyResult<PyRecordsIterator> {
+        let shape = match shape {
+            Some(shape) => JsonShape::from_name(shape)?,
+            None => JsonShape::Raw,
+        };
 
-    fn __iter__(mut slf: PyRefMut<Self>) -> PyResult<PyRecordsIterator> {
-        slf.records()
+        let mut iterator = self.records_iterator(OutputFormat::JSON(shape))?;
+        iterator.sections = self.section_filter(include_fields, exclude_fields);
+        iterator.set_filters(providers, r#where, keywords_mask)?;
+
+        if let Some(token) = resume_from {
+            iterator.resume_from(parse_checkpoint(token)?)?;
+        }
+
+        Ok(iterator)
     }
-    fn __next__(_slf: PyRefMut<Self>) -> PyResult<Option<PyObject>> {
-        Err(PyErr::new::<PyNotImplementedError, _>("Using `next()` over `PyEvtxParser` is not supported. Try iterating over `PyEvtxParser(...).records()`"))
+
+    /// records_ecs(self, /)
+    /// --
+    ///
+    /// Returns an iterator that yields records as JSON, mapped to Elastic Common Schema fields
+    /// the way Winlogbeat does (`@timestamp`, `event.code`, `host.name`, `winlog.provider_name`,
+    /// `winlog.event_data.*` and so on).
+    ///
+    /// Only the most common fields are mapped, fields missing from a record are left out.
+    fn records_ecs(&mut self) -> PyResult<PyRecordsIterator> {
+        self.records_iterator(OutputFormat::ECS)
     }
-}
 
-impl PyEvtxParser {
-    fn records_iterator(&mut self, output_format: OutputFormat) -> PyResult<PyRecordsIterator> {
-        let inner = match self.inner.take() {
-            Some(inner) => inner,
-            None => {
-                return Err(PyErr::new::<PyRuntimeError, _>(
-                    "PyEvtxParser can only be used once",
-                ));
-            }
-        };
+    /// records_typed(self, output_format='xml', /)
+    /// --
+    ///
+    /// Returns an iterator that yields `PyEvtxRecord` objects rather than dicts,
+    /// with typed `event_record_id`, `timestamp` and `data` attributes, and an attribute
+    /// for each of the optional keys of record dicts (`None` when the key would be missing).
+    ///
+    /// Args:
+    ///     `output_format` (str, optional): `xml` (the default) or `json`.
+    #[pyo3(signature = (output_format="xml"))]
+    fn records_typed(&mut self, output_format: &str) -> PyResult<PyRecordsIterator> {
+        let output_format = OutputFormat::from_name(output_format)?;
 
-        Ok(PyRecordsIterator {
-            inner: inner.into_chunks(),
-            records_iter: Vec::new().into_iter(),
-            settings: Arc::new(self.configuration.clone()),
-            output_format,
-        })
+        let mut iterator = self.records_iterator(output_format)?;
+        iterator.typed = true;
+
+        Ok(iterator)
     }
-}
 
-fn record_to_pydict(record: SerializedEvtxRecord<String>, py: Python) -> PyResult<Bound<'_, PyDict>> {
-    let pyrecord = PyDict::new(py);
+    /// records_msgpack(self, /)
+    /// --
+    ///
+    /// Returns an iterator that yields each record as MessagePack encoded `bytes`,
+    /// which are much cheaper to produce and to ship than JSON strings.
+    ///
+    /// Each one decodes to a map laid out like the dicts yielded by `records()`,
+    /// except that `data` is the record's structured form (as found in the JSON output)
+    /// rather than a string, and that `data_size` is never included.
+    ///
+    /// This uses the `rmp` and `rmp-serde` crates.
+    fn records_msgpack(&mut self) -> PyResult<PyRecordsIterator> {
+        let mut iterator = self.records_iterator(OutputFormat::JSON(JsonShape::Raw))?;
+        iterator.msgpack = true;
 
-    pyrecord.set_item("event_record_id", record.event_record_id)?;
-    pyrecord.set_item("timestamp", format!("{}", record.timestamp))?;
-    pyrecord.set_item("data", record.data)?;
-    Ok(pyrecord)
-}
+        Ok(iterator)
+    }
 
-fn record_to_pyobject(
-    r: Result<SerializedEvtxRecord<String>, EvtxError>,
-    py: Python,
-) -> PyResult<PyObject> {
-    match r {
-        Ok(r) => match record_to_pydict(r, py) {
-            Ok(dict) => Ok(dict.into_pyobject(py)?.into()),
-            Err(e) => Ok(e.into_pyobject(py)?.into()),
-        },
-        Err(e) => Err(PyEvtxError(e).into()),
+    /// records_text(self, /)
+    /// --
+    ///
+    /// Returns an iterator that yields each record as a dict like the ones yielded by `records()`,
+    /// except that `data` holds the text content of the record's data values (everything
+    /// but the `System` section), one value per line, without element names, attributes
+    /// or XML escaping.
+    ///
+    /// This is meant for full-text indexing and keyword searches of event payloads
+    /// (such as command lines), which markup and escaping get in the way of.
+    fn records_text(&mut self) -> PyResult<PyRecordsIterator> {
+        let mut iterator = self.records_iterator(OutputFormat::JSON(JsonShape::Raw))?;
+        iterator.text = true;
+
+        Ok(iterator)
     }
-}
 
-#[pyclass]
+    /// records_extract(self, pointers, shape='raw', /)
+    /// --
+    ///
+    /// Returns an iterator that yields, for each record, a dict mapping each of `pointers`
+    /// to the value it points to in the record's JSON structure (as Python objects),
+    /// or to `None` if there is no such value.
+    ///
+    /// Values are extracted before records are converted to Python objects,
+    /// which is much faster than parsing the output of `records_json()`.
+    ///
+    /// Args:
+    ///     `pointers` (list[str]): JSON pointers (RFC 6901) into the record,
+    ///            such as `/Event/System/EventID`.
+    ///
+    ///     `shape` (str, optional): the layout of the JSON structure, as in `records_json()`.
+    #[pyo3(signature = (pointers, shape=None))]
+    fn records_extract(
+        &mut self,
+        pointers: Vec<String>,
+        shape: Option<&str>,
+    ) -> PyResult<PyRecordsIterator> {
+        validate_pointers(&pointers)?;
+
+        let shape = match shape {
+            Some(shape) => JsonShape::from_name(shape)?,
+            None => JsonShape::Raw,
+        };
+
+        let mut iterator = self.records_iterator(OutputFormat::JSON(shape))?;
+        iterator.pointers = Some(pointers);
+
+        Ok(iterator)
+    }
+
+    /// records_arrow_batches(self, batch_size, fields, /)
+    /// --
+    ///
+    /// Returns an iterator that yields records in batches of `batch_size` (the last one may
+    /// be smaller), each a dict mapping column names to lists of values, one per record.
+    ///
+    /// The `event_record_id` and `timestamp` columns are always included, followed by
+    /// a column for each of `fields`. Within a batch, the values of a column all share
+    /// the narrowest type holding them: `bool`, `int`, `float`, or `str` (nested and mixed
+    /// values are rendered as JSON text). Missing values are `None`.
+    ///
+    /// Batches can be turned into columns cheaply, e.g. with `pyarrow.RecordBatch.from_pydict`
+    /// or `pandas.DataFrame`, which is much faster than building them from per-record dicts.
+    /// Failed records are skipped.
+    ///
+    /// Args:
+    ///     `batch_size` (int): the number of records per batch.
+    ///
+    ///     `fields` (list[str]): JSON pointers (RFC 6901) into the record,
+    ///            such as `/Event/System/EventID`, as in `records_extract()`.
+    fn records_arrow_batches(
+        &mut self,
+        batch_size: usize,
+        fields: Vec<String>,
+    ) -> PyResult<PyRecordsIterator> {
+        if batch_size == 0 {
+            return Err(PyErr::new::<PyValueError, _>(
+                "`batch_size` must be greater than 0",
+            ));
+        }
+        validate_pointers(&fields)?;
+
+        let mut iterator = self.records_iterator(OutputFormat::JSON(JsonShape::Raw))?;
+        iterator.pointers = Some(fields);
+        iterator.batch_size = Some(batch_size);
+
+        Ok(iterator)
+    }
+
+    /// records_tuples(self, fields, output_format='xml', /)
+    /// --
+    ///
+    /// Returns an iterator that yields a plain tuple per record, holding `fields` in the
+    /// order they are given, which skips building a dict for every record.
+    ///
+    /// For example, `records_tuples(['event_record_id', 'timestamp', 'event_id', 'data'])`
+    /// yields `(event_record_id, timestamp, event_id, data)` tuples.
+    /// The order of the values is exactly that of `fields`, whatever the other options,
+    /// so records can be indexed positionally (or unpacked) safely.
+    ///
+    /// Failed records raise like they do when iterating with `records()`.
+    ///
+    /// Args:
+    ///     `fields` (list[str]): any of `event_record_id`, `timestamp` and `data`
+    ///            (as in the dicts yielded by `records()`), and of `event_id`, `version`,
+    ///            `provider`, `provider_guid` and `channel` (from the `<System>` element,
+    ///            `None` when missing). Each field may only be listed once.
+    ///
+    ///     `output_format` (str, optional): `xml` (the default) or `json`, the format of `data`.
+    #[pyo3(signature = (fields, output_format="xml"))]
+    fn records_tuples(
+        &mut self,
+        fields: Vec<String>,
+        output_format: &str,
+    ) -> PyResult<PyRecordsIterator> {
+        let fields = tuple_fields(&fields)?;
+        let output_format = OutputFormat::from_name(output_format)?;
+
+        let mut iterator = self.records_iterator(output_format)?;
+        iterator.tuple_fields = Some(fields);
+
+        Ok(iterator)
+    }
+
+    /// records_matching(self, pattern, output_format='xml', case_insensitive=False, invert=False, /)
+    /// --
+    ///
+    /// Returns an iterator that yields only the records whose serialized data matches
+    /// the regular expression `pattern` (see https://docs.rs/regex for the syntax).
+    ///
+    /// Filtering takes place before records are converted to Python objects,
+    /// which is much faster than filtering the output of `records()`.
+    ///
+    /// Args:
+    ///     `pattern` (str): the regular expression to search for.
+    ///
+    ///     `output_format` (str, optional): `xml` (the default) or `json`,
+    ///            the format records are both matched against and yielded in.
+    ///
+    ///     `case_insensitive` (bool, optional): match letters regardless of their case.
+    ///
+    ///     `invert` (bool, optional): yield only the records which do not match.
+    #[pyo3(signature = (pattern, output_format="xml", case_insensitive=false, invert=false))]
+    fn records_matching(
+        &mut self,
+        pattern: &str,
+        output_format: &str,
+        case_insensitive: bool,
+        invert: bool,
+    ) -> PyResult<PyRecordsIterator> {
+        let output_format = OutputFormat::from_name(output_format)?;
+
+        let regex = RegexBuilder::new(pattern)
+            .case_insensitive(case_insensitive)
+            .build()
+            .map_err(|e| PyErr::new::<PyValueError, _>(format!("{}", e)))?;
+
+        let mut iterator = self.records_iterator(output_format)?;
+        iterator.text_filter = Some(TextFilter { regex, invert });
+
+        Ok(iterator)
+    }
+
+    /// records_failed(self, output_format='xml', /)
+    /// --
+    ///
+    /// Returns an iterator that yields only the records which fail to be rendered,
+    /// as the error dicts described under `report_record_errors` (whether it is set or not),
+    /// to find out which events of a file can't be rendered and why.
+    ///
+    /// Records fail when their BinXml can't be deserialized (e.g. because of a missing or
+    /// corrupted template), or can't be serialized to `output_format`, or trip one of
+    /// the checks of the parser (`max_depth`, `xml_invalid_chars='error'`).
+    ///
+    /// Args:
+    ///     `output_format` (str, optional): `xml` (the default) or `json`,
+    ///            the format records are rendered to.
+    #[pyo3(signature = (output_format="xml"))]
+    fn records_failed(&mut self, output_format: &str) -> PyResult<PyRecordsIterator> {
+        let output_format = OutputFormat::from_name(output_format)?;
+
+        let mut iterator = self.records_iterator(output_format)?;
+        iterator.failed_only = true;
+
+        Ok(iterator)
+    }
+
+    /// records_with_templates(self, output_format='xml', /)
+    /// --
+    ///
+    /// Returns an iterator that yields only the records holding at least one template
+    /// instance (see `record_template_instances()`), which are the ones template-based
+    /// rendering applies to, so other records can be skipped entirely.
+    ///
+    /// Each record dict has an extra `template_guids` key, the GUIDs of the templates
+    /// of the record's instances, in document order.
+    ///
+    /// Args:
+    ///     `output_format` (str, optional): `xml` (the default) or `json`.
+    #[pyo3(signature = (output_format="xml"))]
+    fn records_with_templates(&mut self, output_format: &str) -> PyResult<PyRecordsIterator> {
+        let output_format = OutputFormat::from_name(output_format)?;
+
+        let mut iterator = self.records_iterator(output_format)?;
+        iterator.with_templates = true;
+
+        Ok(iterator)
+    }
+
+    /// records_between(self, start_id, end_id, output_format='xml', /)
+    /// --
+    ///
+    /// Returns an iterator that yields only the records with
+    /// `start_id <= event_record_id <= end_id`.
+    ///
+    /// Chunks whose record ids (as recorded in their header) are all outside of the range
+    /// are skipped without being parsed.
+    ///
+    /// Args:
+    ///     `start_id` (int): the first event record id to yield.
+    ///
+    ///     `end_id` (int): the last event record id to yield.
+    ///
+    ///     `output_format` (str, optional): `xml` (the default) or `json`.
+    #[pyo3(signature = (start_id, end_id, output_format="xml"))]
+    fn records_between(
+        &mut self,
+        start_id: u64,
+        end_id: u64,
+        output_format: &str,
+    ) -> PyResult<PyRecordsIterator> {
+        let output_format = OutputFormat::from_name(output_format)?;
+
+        let mut iterator = self.records_iterator(output_format)?;
+        iterator.record_ids = Some(start_id..=end_id);
+
+        Ok(iterator)
+    }
+
+    /// stream_to_queue(self, queue, output_format='json', /)
+    /// --
+    ///
+    /// Parses the records in a background thread, and puts each of them on `queue`
+    /// (a `queue.Queue`), followed by `None` once done. Records are put as the dicts
+    /// yielded by `records()` or `records_json()`; if iteration fails, the exception object
+    /// is put ahead of the `None`.
+    ///
+    /// The thread blocks while the queue is full, so its `maxsize` bounds the records
+    /// parsed ahead of the consumers. Returns a `PyQueueStream` handle, whose `stop()` method
+    /// stops the thread without waiting for the queue to be drained.
+    ///
+    /// If putting on the queue fails (other than with `queue.Full`), the thread exits
+    /// and the error is raised by the next call to `join()` or `stop()`. Consumers waiting
+    /// on the queue should use a timeout, as the final `None` can't be put either.
+    ///
+    /// Args:
+    ///     `queue` (queue.Queue): the queue to put records on. Raises `TypeError`
+    ///            if it has no callable `put` method.
+    ///
+    ///     `output_format` (str, optional): `json` (the default) or `xml`.
+    #[pyo3(signature = (queue, output_format="json"))]
+    fn stream_to_queue(
+        &mut self,
+        py: Python<'_>,
+        queue: PyObject,
+        output_format: &str,
+    ) -> PyResult<PyQueueStream> {
+        let output_format = OutputFormat::from_name(output_format)?;
+
+        let has_put = queue
+            .bind(py)
+            .getattr("put")
+            .is_ok_and(|put| put.is_callable());
+        if !has_put {
+            return Err(PyErr::new::<PyTypeError, _>(
+                "`queue` must have a callable `put` method, such as a `queue.Queue`",
+            ));
+        }
+
+        let mut iterator = self.records_iterator(output_format)?;
+        let full = py.import("queue")?.getattr("Full")?.unbind();
+
+        let stopped = Arc::new(AtomicBool::new(false));
+        let thread_stopped = Arc::clone(&stopped);
+
+        let handle = std::thread::spawn(move || {
+            let put = |item: PyObject| put_until_stopped(&queue, &full, item, &thread_stopped);
+
+            loop {
+                let item = match iterator.next() {
+                    Ok(Some(record)) => record,
+                    Ok(None) => break,
+                    Err(e) => {
+                        if put(Python::with_gil(|py| e.into_value(py).into_any()))? {
+                            break;
+                        }
+                        return Ok(());
+                    }
+                };
+
+                if !put(item)? {
+                    return Ok(());
+                }
+            }
+
+            put(Python::with_gil(|py| py.None()))?;
+            Ok(())
+        });
+
+        Ok(PyQueueStream {
+            stopped,
+            handle: Some(handle),
+        })
+    }
+
+    /// to_json_array(self, path=None, /)
+    /// --
+    ///
+    /// Renders every record as JSON (like `records_json()`) into a single JSON array document,
+    /// for tools which expect one JSON document rather than JSON lines.
+    ///
+    /// Returns the document as a `str`, or writes it to `path` and returns `None`.
+    /// The document is written to `path` with a `.partial` suffix first, and renamed
+    /// to `path` only once complete: on error, `path` is left as it was.
+    /// As with `records_json()`, records which fail to deserialize are left out
+    /// (see `records_failed()`), while a record which fails to be rendered raises.
+    ///
+    /// The `str` holds every record at once, which takes several times the size of the file:
+    /// for large files, pass a `path` (records are then written one at a time),
+    /// or iterate over `records_json()` instead.
+    ///
+    /// Args:
+    ///     `path` (str or os.PathLike, optional): the file to write the document to.
+    #[allow(clippy::wrong_self_convention)]
+    #[pyo3(signature = (path=None))]
+    fn to_json_array(&mut self, path: Option<PathBuf>) -> PyResult<Option<String>> {
+        let mut iterator = self.records_iterator(OutputFormat::JSON(JsonShape::Raw))?;
+
+        match path {
+            Some(path) => {
+                // Written next to `path` and moved over it once complete,
+                // so a failure never leaves a truncated document behind.
+                let mut partial_name = path.file_name().unwrap_or_default().to_os_string();
+                partial_name.push(".partial");
+                let partial_path = path.with_file_name(partial_name);
+
+                let written = File::create(&partial_path)
+                    .map_err(PyErr::from)
+                    .and_then(|file| {
+                        let mut writer = BufWriter::new(file);
+                        iterator.write_json_array(&mut writer)?;
+                        writer.flush()?;
+                        Ok(())
+                    })
+                    .and_then(|()| std::fs::rename(&partial_path, &path).map_err(PyErr::from));
+
+                if written.is_err() {
+                    let _ = std::fs::remove_file(&partial_path);
+                }
+                written.map(|()| None)
+            }
+            None => {
+                let mut document = Vec::new();
+                iterator.write_json_array(&mut document)?;
+
+                Ok(Some(
+                    String::from_utf8(document).expect("records are serialized to UTF-8"),
+                ))
+            }
+        }
+    }
+
+    /// verify_chunks(self, /)
+    /// --
+    ///
+    /// Returns an iterator that yields a
+    /// `(chunk_number, stored_checksums, computed_checksums, ok)` tuple for every chunk,
+    /// without parsing its records, so iteration can stop at the first corrupt chunk.
+    ///
+    /// Checksums are `(header_checksum, events_checksum)` tuples.
+    /// Chunks flagged as having no checksums report `0` for both.
+    fn verify_chunks(&mut self) -> PyResult<PyChunkChecksumsIterator> {
+        let (inner, following) = self.take_parsers()?;
+
+        // Mismatches are what we're after, so they must not fail reading the chunk.
+        let settings = self.configuration.clone().validate_checksums(false);
+        let mut parsers = Some(inner)
+            .into_iter()
+            .chain(following)
+            .map(|parser| parser.with_configuration(settings.clone()));
+
+        Ok(PyChunkChecksumsIterator {
+            inner: parsers.next().expect("there is at least one parser"),
+            following: parsers.collect::<Vec<_>>().into_iter(),
+            chunks: self.chunks.clone(),
+        })
+    }
+
+    /// chunk_stats(self, /)
+    /// --
+    ///
+    /// Returns a list of dicts describing the caches of each chunk, with the following keys:
+    /// `chunk_number`, `first_record_id` and `last_record_id` (from the chunk header),
+    /// `string_cache_entries` (the number of names in the chunk's common string table)
+    /// and `template_cache_entries` (the number of template definitions in its template table).
+    ///
+    /// The caches are built by `evtx` when a chunk is parsed, no record is serialized.
+    /// Unusual figures can point to crafted or corrupted chunks.
+    ///
+    /// This does not consume the parser, which can still be iterated afterwards.
+    fn chunk_stats<'py>(&mut self, py: Python<'py>) -> PyResult<Bound<'py, PyList>> {
+        let settings = Arc::new(self.configuration.clone());
+        let stats = PyList::empty(py);
+
+        self.scan_chunks(|chunk_id, mut chunk| {
+            let chunk = chunk.parse(settings.clone()).map_err(|source| {
+                PyEvtxError(EvtxError::FailedToParseChunk { chunk_id, source })
+            })?;
+
+            let chunk_stats = PyDict::new(py);
+            chunk_stats.set_item("chunk_number", chunk_id)?;
+            chunk_stats.set_item("first_record_id", chunk.header.first_event_record_id)?;
+            chunk_stats.set_item("last_record_id", chunk.header.last_event_record_id)?;
+            chunk_stats.set_item("string_cache_entries", chunk.string_cache.len())?;
+            chunk_stats.set_item("template_cache_entries", chunk.template_table.len())?;
+            stats.append(chunk_stats)
+        })?;
+
+        Ok(stats)
+    }
+
+    /// chunk_offsets(self, /)
+    /// --
+    ///
+    /// Returns the byte offset of each chunk in range, in file order.
+    ///
+    /// Chunks start right after the file header block (4096 bytes, unless the header
+    /// gives another size, as some third-party writers do) and are 65536 bytes each,
+    /// only chunks which fit in the file are listed, and empty (zeroed) chunks are skipped.
+    /// Chunks which fail to be read (such as corrupted ones) are still listed.
+    ///
+    /// Along with `parse_chunk_bytes()`, this is the map needed to distribute the chunks of a file
+    /// between worker processes: `data[offset:offset + 65536]` is the chunk at `offset`.
+    ///
+    /// Raises `ValueError` with `multi_file`, as offsets would be ambiguous between the files.
+    ///
+    /// This does not consume the parser, which can still be iterated afterwards.
+    fn chunk_offsets(&mut self) -> PyResult<Vec<u64>> {
+        if !self.following.is_empty() {
+            return Err(PyErr::new::<PyValueError, _>(
+                "`chunk_offsets` is not supported for `multi_file` inputs",
+            ));
+        }
+
+        let first_chunk_offset = self.first_chunk_offset;
+        let mut offsets = vec![];
+        self.scan_chunk_results(|chunk_number, _| {
+            offsets.push(chunk_offset(chunk_number, first_chunk_offset));
+            Ok(())
+        })?;
+
+        Ok(offsets)
+    }
+
+    /// estimate_memory(self, /)
+    /// --
+    ///
+    /// Returns a dict of rough estimates of the memory needed to parse this file
+    /// with the current settings, in bytes, to pick safe settings on constrained machines.
+    ///
+    /// Keys:
+    ///     `input`: the input itself, when it was given as `bytes` (which are copied).
+    ///     `chunk`: a chunk's data along with its string and template caches.
+    ///     `record`: the largest record of the file, once deserialized and serialized.
+    ///     `iterator_peak`: the peak while iterating (`records()` and the like),
+    ///            which handles one chunk and one record at a time, whatever `number_of_threads`.
+    ///     `parallel_peak`: the peak of `evtx`'s own multithreaded iteration
+    ///            (as used by `benchmark()`), which handles a chunk per thread at once.
+    ///     `num_threads`: the number of threads the parallel estimate is for.
+    ///
+    /// Only the record headers are read, to find the largest record.
+    /// Actual usage varies with the records' templates, the figures are meant as upper bounds
+    /// for typical files rather than exact measurements.
+    ///
+    /// This does not consume the parser, which can still be iterated afterwards.
+    fn estimate_memory<'py>(&mut self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let mut largest_record = 0;
+
+        self.scan_chunks(|_, chunk| {
+            for record in iter_raw_records(&chunk) {
+                largest_record = largest_record.max(record.data.len() as u64);
+            }
+
+            Ok(())
+        })?;
+
+        let estimate = MemoryEstimate {
+            input: self.buffered_input,
+            largest_record,
+            num_threads: resolved_num_threads(&self.configuration) as u64,
+            extract_binary: self.extract_binary,
+        };
+
+        let estimates = PyDict::new(py);
+        estimates.set_item("input", estimate.input)?;
+        estimates.set_item("chunk", estimate.chunk())?;
+        estimates.set_item("record", estimate.record())?;
+        estimates.set_item("iterator_peak", estimate.iterator_peak())?;
+        estimates.set_item("parallel_peak", estimate.parallel_peak())?;
+        estimates.set_item("num_threads", estimate.num_threads)?;
+        Ok(estimates)
+    }
+
+    /// time_range(self, /)
+    /// --
+    ///
+    /// Returns an `(oldest, newest)` tuple of the record timestamps (as `datetime` objects),
+    /// or `None` if there are no records.
+    ///
+    /// Only the record headers are read (no XML/JSON serialization takes place),
+    /// but every record is considered, so the result is exact even for dirty files
+    /// where records are out of order.
+    ///
+    /// This does not consume the parser, which can still be iterated afterwards.
+    fn time_range(&mut self) -> PyResult<Option<(DateTime<Utc>, DateTime<Utc>)>> {
+        let mut range: Option<(DateTime<Utc>, DateTime<Utc>)> = None;
+
+        self.scan_chunks(|_, chunk| {
+            for record in iter_raw_records(&chunk) {
+                let timestamp = record.header.timestamp;
+
+                range = Some(match range {
+                    Some((oldest, newest)) => (oldest.min(timestamp), newest.max(timestamp)),
+                    None => (timestamp, timestamp),
+                });
+            }
+
+            Ok(())
+        })?;
+
+        Ok(range)
+    }
+
+    /// event_id_histogram(self, sort_by_count=False, /)
+    /// --
+    ///
+    /// Returns a dict mapping each EventID to the number of records carrying it.
+    ///
+    /// Records are not rendered: their `EventID` is read straight from the BinXml tokens,
+    /// which stops at the end of `<System>`. Records which can't be read are left out,
+    /// and counted in the `record_errors` attribute of the parser.
+    ///
+    /// Args:
+    ///     `sort_by_count` (bool, optional): order the dict by descending count,
+    ///            rather than by ascending EventID.
+    ///
+    /// This does not consume the parser, which can still be iterated afterwards.
+    #[pyo3(signature = (sort_by_count=false))]
+    #[allow(clippy::result_large_err)]
+    fn event_id_histogram<'py>(
+        &mut self,
+        py: Python<'py>,
+        sort_by_count: bool,
+    ) -> PyResult<Bound<'py, PyDict>> {
+        let mut histogram = BTreeMap::new();
+
+        self.scan_records(|record| {
+            if let Some(event_id) = RecordMetadata::from_record(&record)?.event_id {
+                *histogram.entry(event_id).or_insert(0_u64) += 1;
+            }
+
+            Ok(())
+        })?;
+
+        let mut counts: Vec<_> = histogram.into_iter().collect();
+        if sort_by_count {
+            counts.sort_by(|(_, a), (_, b)| b.cmp(a));
+        }
+
+        let dict = PyDict::new(py);
+        for (event_id, count) in counts {
+            dict.set_item(event_id, count)?;
+        }
+
+        Ok(dict)
+    }
+
+    /// schema_keys(self, /)
+    /// --
+    ///
+    /// Returns the distinct `(provider_guid, event_id, version)` tuples of the records, sorted.
+    /// These identify the event definitions needed to render the file.
+    ///
+    /// Providers without a GUID (such as classic event log sources) are identified by name.
+    /// Records without a `Version` are reported with version `0`.
+    ///
+    /// As in `event_id_histogram()`, only the `<System>` fields are read, without rendering records,
+    /// and records which can't be read are left out (see `record_errors`).
+    ///
+    /// This does not consume the parser, which can still be iterated afterwards.
+    #[allow(clippy::result_large_err)]
+    fn schema_keys(&mut self) -> PyResult<Vec<(String, u64, u64)>> {
+        let mut keys = BTreeSet::new();
+
+        self.scan_records(|record| {
+            let metadata = RecordMetadata::from_record(&record)?;
+
+            if let (Some(provider), Some(event_id)) = (
+                metadata.provider_guid.or(metadata.provider_name),
+                metadata.event_id,
+            ) {
+                keys.insert((provider, event_id, metadata.version.unwrap_or(0)));
+            }
+
+            Ok(())
+        })?;
+
+        Ok(keys.into_iter().collect())
+    }
+
+    /// channels(self, /)
+    /// --
+    ///
+    /// Returns the set of distinct `Channel` values of the records
+    /// (forwarded event files often hold records of many channels).
+    ///
+    /// As in `event_id_histogram()`, only the `<System>` fields are read, without rendering records,
+    /// and records which can't be read are left out (see `record_errors`).
+    ///
+    /// This does not consume the parser, which can still be iterated afterwards.
+    #[allow(clippy::result_large_err)]
+    fn channels(&mut self) -> PyResult<BTreeSet<String>> {
+        let mut channels = BTreeSet::new();
+
+        self.scan_records(|record| {
+            if let Some(channel) = RecordMetadata::from_record(&record)?.channel {
+                channels.insert(channel);
+            }
+
+            Ok(())
+        })?;
+
+        Ok(channels)
+    }
+
+    /// summarize(self, /)
+    /// --
+    ///
+    /// Returns a summary of the file, gathered in a single pass, as a dict with the following keys:
+    ///
+    ///     `record_count`: the number of records which could be read (and are within `max_depth`).
+    ///     `record_errors`: the number of records which could not, which are left out
+    ///            of the other keys.
+    ///     `time_range`: the `(oldest, newest)` record timestamps, or `None`, as in `time_range()`.
+    ///     `event_id_histogram`: the number of records of each EventID, as in `event_id_histogram()`.
+    ///     `providers`: the distinct provider names (or GUIDs, for providers without a name), sorted.
+    ///     `channels`: the distinct `Channel` values, sorted.
+    ///     `bad_chunks`: a `(chunk_number, error)` tuple for each chunk which failed to be read
+    ///            or has mismatching checksums. Unlike the other methods, these don't raise.
+    ///
+    /// Records are not rendered (only their `<System>` fields are read), and a single pass
+    /// is cheaper than calling the individual methods in turn.
+    ///
+    /// This does not consume the parser, which can still be iterated afterwards.
+    fn summarize<'py>(&mut self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let settings = Arc::new(self.configuration.clone().validate_checksums(false));
+        let max_depth = self.max_depth;
+
+        let mut record_count = 0_u64;
+        let mut time_range: Option<(DateTime<Utc>, DateTime<Utc>)> = None;
+        let mut histogram = BTreeMap::new();
+        let mut providers = BTreeSet::new();
+        let mut channels = BTreeSet::new();
+        let mut bad_chunks = vec![];
+        let mut record_errors = 0_u64;
+
+        self.scan_chunk_results(|chunk_id, chunk| {
+            let mut chunk = match chunk {
+                Ok(chunk) => chunk,
+                Err(e) => {
+                    bad_chunks.push((chunk_id, e.to_string()));
+                    return Ok(());
+                }
+            };
+
+            if !chunk.validate_checksum() {
+                bad_chunks.push((chunk_id, "Checksum mismatch".to_string()));
+            }
+
+            let mut chunk = match chunk.parse(settings.clone()) {
+                Ok(chunk) => chunk,
+                Err(e) => {
+                    bad_chunks.push((chunk_id, e.to_string()));
+                    return Ok(());
+                }
+            };
+
+            for record in chunk.iter() {
+                let read = record
+                    .ok()
+                    .filter(|record| {
+                        max_depth.is_none_or(|max_depth| !exceeds_depth(record, max_depth))
+                    })
+                    .and_then(|record| {
+                        let metadata = RecordMetadata::from_record(&record).ok()?;
+                        Some((record, metadata))
+                    });
+                let (record, metadata) = match read {
+                    Some(read) => read,
+                    None => {
+                        record_errors += 1;
+                        continue;
+                    }
+                };
+
+                let timestamp = record.timestamp;
+                time_range = Some(match time_range {
+                    Some((oldest, newest)) => (oldest.min(timestamp), newest.max(timestamp)),
+                    None => (timestamp, timestamp),
+                });
+                record_count += 1;
+
+                if let Some(event_id) = metadata.event_id {
+                    *histogram.entry(event_id).or_insert(0_u64) += 1;
+                }
+                if let Some(provider) = metadata.provider_name.or(metadata.provider_guid) {
+                    providers.insert(provider);
+                }
+                if let Some(channel) = metadata.channel {
+                    channels.insert(channel);
+                }
+            }
+
+            Ok(())
+        })?;
+
+        let summary = PyDict::new(py);
+        summary.set_item("record_count", record_count)?;
+        summary.set_item("time_range", time_range)?;
+        summary.set_item("event_id_histogram", histogram)?;
+        summary.set_item("providers", providers.into_iter().collect::<Vec<_>>())?;
+        summary.set_item("channels", channels.into_iter().collect::<Vec<_>>())?;
+        summary.set_item("bad_chunks", bad_chunks)?;
+        summary.set_item("record_errors", record_errors)?;
+        self.record_errors = record_errors;
+        Ok(summary)
+    }
+
+    /// count_matching(self, event_ids=None, providers=None, from_time=None, to_time=None, /)
+    /// --
+    ///
+    /// Returns the number of records matching all of the given filters.
+    ///
+    /// Records are not rendered, only their timestamps and `<System>` fields are read.
+    /// Records which can't be read are not counted, but reported in `record_errors`.
+    ///
+    /// This does not consume the parser, which can still be iterated afterwards.
+    ///
+    /// Args:
+    ///     `event_ids` (list[int], optional): count only records with one of these EventIDs.
+    ///
+    ///     `providers` (list[str], optional): count only records of these providers,
+    ///            given by name or GUID (as in `records()`).
+    ///
+    ///     `from_time` (datetime, optional): count only records written at or after this time.
+    ///
+    ///     `to_time` (datetime, optional): count only records written at or before this time.
+    #[pyo3(signature = (event_ids=None, providers=None, from_time=None, to_time=None))]
+    #[allow(clippy::result_large_err)]
+    fn count_matching(
+        &mut self,
+        event_ids: Option<Vec<u64>>,
+        providers: Option<Vec<String>>,
+        from_time: Option<DateTime<Utc>>,
+        to_time: Option<DateTime<Utc>>,
+    ) -> PyResult<u64> {
+        let providers = providers.map(ProviderFilter::new);
+        let mut count = 0;
+
+        self.scan_records(|record| {
+            if from_time.is_some_and(|from| record.timestamp < from)
+                || to_time.is_some_and(|to| record.timestamp > to)
+            {
+                return Ok(());
+            }
+
+            if event_ids.is_some() || providers.is_some() {
+                let metadata = RecordMetadata::from_record(&record)?;
+
+                let event_id_matches = match (&event_ids, metadata.event_id) {
+                    (Some(event_ids), Some(event_id)) => event_ids.contains(&event_id),
+                    (Some(_), None) => false,
+                    (None, _) => true,
+                };
+
+                if !event_id_matches
+                    || !providers
+                        .as_ref()
+                        .is_none_or(|p| p.accepts_metadata(&metadata))
+                {
+                    return Ok(());
+                }
+            }
+
+            count += 1;
+            Ok(())
+        })?;
+
+        Ok(count)
+    }
+
+    /// The number of records which failed to deserialize (or were nested deeper than `max_depth`)
+    /// in the last scan of the parser, by a method which does not consume it (such as
+    /// `event_id_histogram()`, `count_matching()` or `summarize()`).
+    /// These records are left out of the results of such methods.
+    #[getter]
+    fn record_errors(&self) -> u64 {
+        self.record_errors
+    }
+
+    /// record_hexdump(self, record_id, /)
+    /// --
+    ///
+    /// Returns a `hexdump -C` style dump of the raw bytes of the record with the given
+    /// event record id (including its header), with offsets relative to the start of the record.
+    ///
+    /// Raises `KeyError` if there is no such record.
+    ///
+    /// This does not consume the parser, which can still be iterated afterwards.
+    fn record_hexdump(&mut self, record_id: u64) -> PyResult<String> {
+        let mut dump = None;
+
+        self.scan_chunks(|_, chunk| {
+            if dump.is_none() {
+                dump = iter_raw_records(&chunk)
+                    .find(|record| record.header.event_record_id == record_id)
+                    .map(|record| hexdump(record.data));
+            }
+
+            Ok(())
+        })?;
+
+        dump.ok_or_else(|| PyErr::new::<PyKeyError, _>(record_id))
+    }
+
+    /// record_template_instances(self, record_id, /)
+    /// --
+    ///
+    /// Returns the template instances of the record with the given event record id,
+    /// as a list of dicts with a `template_offset` key (the offset of the template definition
+    /// within its chunk) and a `values` key (the substitution values, as Python objects).
+    ///
+    /// Most records are a single template instance, but records embedding other events
+    /// (in BinXml values) have an instance for each of them, listed after the instance
+    /// holding them. In `values`, the embedded BinXml itself is `None`.
+    ///
+    /// Raises `KeyError` if there is no such record.
+    ///
+    /// This does not consume the parser, which can still be iterated afterwards.
+    fn record_template_instances(&mut self, record_id: u64) -> PyResult<Py<PyList>> {
+        self.find_record(record_id, |py, record| {
+            let list = PyList::empty(py);
+
+            for instance in template_instances(&record.tokens) {
+                let dict = PyDict::new(py);
+                dict.set_item("template_offset", instance.template_def_offset)?;
+                dict.set_item("values", substitution_values(py, instance)?)?;
+                list.append(dict)?;
+            }
+
+            Ok(list.unbind())
+        })
+    }
+
+    /// record_substitutions(self, record_id, /)
+    /// --
+    ///
+    /// Returns the substitution values of the record with the given event record id
+    /// (the variable part of the record, independent of its templates), as Python objects.
+    ///
+    /// Values are converted to the closest Python type: integers, floats, `bool`, `str`,
+    /// `bytes` for binary values, `datetime` for timestamps, lists for arrays, and `str` for
+    /// GUIDs, SIDs and hex integers. The values of all of the record's template instances
+    /// are included, in document order, with `None` standing for embedded BinXml
+    /// (see `record_template_instances()`).
+    ///
+    /// Raises `KeyError` if there is no such record.
+    ///
+    /// This does not consume the parser, which can still be iterated afterwards.
+    fn record_substitutions(&mut self, record_id: u64) -> PyResult<Py<PyList>> {
+        self.find_record(record_id, |py, record| {
+            let list = PyList::empty(py);
+
+            for instance in template_instances(&record.tokens) {
+                for value in substitution_values(py, instance)? {
+                    list.append(value)?;
+                }
+            }
+
+            Ok(list.unbind())
+        })
+    }
+
+    /// settings(self, /)
+    /// --
+    ///
+    /// Returns a dict of the settings the parser is actually running with,
+    /// with defaults filled in (e.g. `num_threads` is the number of threads used
+    /// rather than `0`), which is useful in bug reports and to check the constructor's arguments.
+    ///
+    /// Keys:
+    ///     `num_threads`, `ansi_codec`, `validate_checksums`, `separate_json_attributes`
+    ///     and `indent`: the settings of the underlying `evtx` parser.
+    ///     `wevt_cache`: whether a WEVT template cache is in use (never, see `has_wevt_support()`).
+    ///     The other keys are the options of these bindings, as passed to the constructor.
+    fn settings<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let configuration = &self.configuration;
+        let num_threads = resolved_num_threads(configuration);
+
+        let settings = PyDict::new(py);
+        settings.set_item("num_threads", num_threads)?;
+        settings.set_item("ansi_codec", configuration.get_ansi_codec().name())?;
+        settings.set_item(
+            "validate_checksums",
+            configuration.should_validate_checksums(),
+        )?;
+        settings.set_item(
+            "separate_json_attributes",
+            configuration.should_separate_json_attributes(),
+        )?;
+        settings.set_item("indent", configuration.should_indent())?;
+        settings.set_item("wevt_cache", false)?;
+
+        settings.set_item("checksum_policy", self.checksum_policy.name())?;
+        settings.set_item("bare", self.bare)?;
+        settings.set_item("extract_binary", self.extract_binary)?;
+        settings.set_item("report_record_errors", self.report_record_errors)?;
+        settings.set_item("max_depth", self.max_depth)?;
+        settings.set_item("include_metadata", self.include_metadata)?;
+        settings.set_item("max_records", self.max_records)?;
+        settings.set_item("timestamp_suffix", self.timestamp_suffix)?;
+        settings.set_item("xml_invalid_chars", self.xml_invalid_chars.name())?;
+        settings.set_item("binary_encoding", self.binary_encoding.name())?;
+        settings.set_item("content_hash", self.content_hash)?;
+        settings.set_item("skip_empty", self.skip_empty)?;
+        settings.set_item("rendering_info", self.rendering_info)?;
+        settings.set_item("read_retries", self.read_retries)?;
+        settings.set_item(
+            "on_chunk_error",
+            self.on_chunk_error.as_ref().map(|f| f.clone_ref(py)),
+        )?;
+        settings.set_item(
+            "ansi_codec_fallbacks",
+            self.ansi_codec_fallbacks
+                .iter()
+                .map(|codec| codec.name())
+                .collect::<Vec<_>>(),
+        )?;
+        Ok(settings)
+    }
+
+    fn __iter__(mut slf: PyRefMut<Self>) -> PyResult<PyRecordsIterator> {
+        slf.records(None, None, None, None, None)
+    }
+    fn __next__(_slf: PyRefMut<Self>) -> PyResult<Option<PyObject>> {
+        Err(PyErr::new::<PyNotImplementedError, _>("Using `next()` over `PyEvtxParser` is not supported. Try iterating over `PyEvtxParser(...).records()`"))
+    }
+}
+
+impl PyEvtxParser {
+    /// The sections to keep in records, leaving out `RenderingInfo` unless `rendering_info` is set.
+    fn section_filter(
+        &self,
+        include: Option<Vec<String>>,
+        mut exclude: Option<Vec<String>>,
+    ) -> Option<SectionFilter> {
+        if !self.rendering_info {
+            exclude
+                .get_or_insert_with(Vec::new)
+                .push(RENDERING_INFO.to_string());
+        }
+
+        SectionFilter::new(include, exclude)
+    }
+
+    /// Calls `f` with every chunk (and its number) in range, without consuming the parser.
+    fn scan_chunks(
+        &mut self,
+        mut f: impl FnMut(u64, EvtxChunkData) -> PyResult<()>,
+    ) -> PyResult<()> {
+        self.scan_chunk_results(|number, chunk| f(number, chunk.map_err(PyEvtxError)?))
+    }
+
+    /// Like `scan_chunks`, but leaves chunks which fail to be read to `f`.
+    fn scan_chunk_results(
+        &mut self,
+        mut f: impl FnMut(u64, Result<EvtxChunkData, EvtxError>) -> PyResult<()>,
+    ) -> PyResult<()> {
+        if self.inner.is_none() {
+            return Err(PyErr::new::<PyRuntimeError, _>(
+                "PyEvtxParser can only be used once",
+            ));
+        }
+
+        for parser in self.inner.iter_mut().chain(self.following.iter_mut()) {
+            let mut chunk_number = self.chunks.start;
+
+            while chunk_number < self.chunks.end {
+                match parser.find_next_chunk(chunk_number) {
+                    Some((chunk, number)) if number < self.chunks.end => {
+                        f(number, chunk)?;
+                        chunk_number = number + 1;
+                    }
+                    _ => break,
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Calls `f` with every record in range, without consuming the parser.
+    /// Records which fail to deserialize or are nested deeper than `max_depth` are skipped,
+    /// like they are when iterating, and counted in `record_errors`.
+    fn scan_records(
+        &mut self,
+        mut f: impl FnMut(EvtxRecord) -> Result<(), EvtxError>,
+    ) -> PyResult<()> {
+        let settings = Arc::new(self.configuration.clone());
+        let max_depth = self.max_depth;
+        let mut record_errors = 0;
+
+        let result = self.scan_chunks(|chunk_id, mut chunk| {
+            let mut chunk = chunk.parse(settings.clone()).map_err(|source| {
+                PyEvtxError(EvtxError::FailedToParseChunk { chunk_id, source })
+            })?;
+
+            for record in chunk.iter() {
+                match record {
+                    Ok(record)
+                        if max_depth.is_none_or(|max_depth| !exceeds_depth(&record, max_depth)) =>
+                    {
+                        f(record).map_err(PyEvtxError)?
+                    }
+                    _ => record_errors += 1,
+                }
+            }
+
+            Ok(())
+        });
+
+        self.record_errors = record_errors;
+        result
+    }
+
+    /// Calls `f` with the record with the given event record id, without consuming the parser.
+    /// Raises `KeyError` if there is no such record.
+    #[allow(clippy::result_large_err)]
+    fn find_record<T>(
+        &mut self,
+        record_id: u64,
+        mut f: impl FnMut(Python<'_>, &EvtxRecord) -> PyResult<T>,
+    ) -> PyResult<T> {
+        let mut result = None;
+
+        self.scan_records(|record| {
+            if result.is_none() && record.event_record_id == record_id {
+                result = Some(Python::with_gil(|py| f(py, &record)));
+            }
+
+            Ok(())
+        })?;
+
+        result.ok_or_else(|| PyErr::new::<PyKeyError, _>(record_id))?
+    }
+
+    /// Moves the parsers out, for an iterator to own them.
+    fn take_parsers(&mut self) -> PyResult<(BoxedParser, Vec<BoxedParser>)> {
+        match self.inner.take() {
+            Some(inner) => Ok((inner, std::mem::take(&mut self.following))),
+            None => Err(PyErr::new::<PyRuntimeError, _>(
+                "PyEvtxParser can only be used once",
+            )),
+        }
+    }
+
+    fn records_iterator(&mut self, output_format: OutputFormat) -> PyResult<PyRecordsIterator> {
+        let (inner, following) = self.take_parsers()?;
+
+        Ok(PyRecordsIterator {
+            inner,
+            following: following.into_iter(),
+            chunks: self.chunks.clone(),
+            records: None,
+            chunk_id: 0,
+            first_chunk_offset: self.first_chunk_offset,
+            read_failure: self.read_failure.clone(),
+            settings: Arc::new(self.configuration.clone()),
+            output_format,
+            bare: self.bare,
+            checksum_policy: self.checksum_policy,
+            extract_binary: self.extract_binary,
+            report_record_errors: self.report_record_errors,
+            max_depth: self.max_depth,
+            include_metadata: self.include_metadata,
+            max_records: self.max_records,
+            timestamp_suffix: self.timestamp_suffix,
+            fallback_settings: self
+                .ansi_codec_fallbacks
+                .iter()
+                .map(|&codec| Arc::new(self.configuration.clone().ansi_codec(codec)))
+                .collect(),
+            xml_invalid_chars: self.xml_invalid_chars,
+            binary_encoding: self.binary_encoding,
+            on_chunk_error: Python::with_gil(|py| {
+                self.on_chunk_error.as_ref().map(|f| f.clone_ref(py))
+            }),
+            content_hash: self.content_hash,
+            skip_empty: self.skip_empty,
+            records_parsed: 0,
+            previous_record_id: None,
+            checksum_mismatches: Vec::new(),
+            record_errors: 0,
+            partial_final_chunk: false,
+            text_filter: None,
+            records_yielded: 0,
+            typed: false,
+            msgpack: false,
+            text: false,
+            failed_only: false,
+            with_templates: false,
+            sections: self.section_filter(None, None),
+            pointers: None,
+            batch_size: None,
+            tuple_fields: None,
+            transform: None,
+            slice: RecordsSlice::default(),
+            record_ids: None,
+            provider_filter: None,
+            expression: None,
+            keywords_mask: None,
+            file_index: 0,
+            chunk_first_record_id: 0,
+            checkpoint: None,
+            resumed: None,
+        })
+    }
+}
+
+/// Converts the substitution values of a template instance to Python objects.
+fn substitution_values<'py>(
+    py: Python<'py>,
+    instance: &BinXmlTemplateRef,
+) -> PyResult<Bound<'py, PyList>> {
+    let values = PyList::empty(py);
+
+    for token in &instance.substitution_array {
+        if let BinXMLDeserializedTokens::Value(value) = token {
+            values.append(value_to_pyobject(py, value)?)?;
+        }
+    }
+
+    Ok(values)
+}
+
+fn parse_checkpoint(token: &[u8]) -> PyResult<Checkpoint> {
+    Checkpoint::from_bytes(token).ok_or_else(|| {
+        PyErr::new::<PyValueError, _>("Invalid checkpoint, expected a token from `checkpoint()`")
+    })
+}
+
+/// Returns the number of threads `evtx` uses, which is the number of cores when set to `0`.
+fn resolved_num_threads(settings: &ParserSettings) -> usize {
+    match *settings.get_num_threads() {
+        0 => std::thread::available_parallelism().map_or(1, |n| n.get()),
+        n => n,
+    }
+}
+
+/// Returns the number of the first chunk starting at or after `offset`.
+fn chunk_number_at(offset: u64, first_chunk_offset: u64) -> u64 {
+    offset
+        .saturating_sub(first_chunk_offset)
+        .div_ceil(EVTX_CHUNK_SIZE)
+}
+
+/// Returns the offset of the chunk in the file.
+fn chunk_offset(chunk_number: u64, first_chunk_offset: u64) -> u64 {
+    first_chunk_offset + chunk_number * EVTX_CHUNK_SIZE
+}
+
+/// Serialized records are usually larger than their BinXml, which is compact and shares
+/// templates across records. This is used to presize the output buffer from the record size,
+/// to avoid reallocating it over and over for large records.
+const SERIALIZED_SIZE_RATIO: usize = 2;
+
+#[allow(clippy::result_large_err)]
+fn into_shaped_json(
+    record: EvtxRecord,
+    shape: JsonShape,
+    sections: Option<&SectionFilter>,
+    settings: &ParserSettings,
+    size: u32,
+) -> Result<SerializedEvtxRecord<String>, EvtxError> {
+    let mut record = record.into_json_value()?;
+    if let Some(sections) = sections {
+        sections.apply(&mut record.data);
+    }
+    shape.apply(&mut record.data);
+
+    Ok(SerializedEvtxRecord {
+        event_record_id: record.event_record_id,
+        timestamp: record.timestamp,
+        data: json_to_string(&record.data, settings, size)?,
+    })
+}
+
+#[allow(clippy::result_large_err)]
+fn into_ecs_json(
+    record: EvtxRecord,
+    settings: &ParserSettings,
+    size: u32,
+) -> Result<SerializedEvtxRecord<String>, EvtxError> {
+    let record = record.into_json_value()?;
+    let ecs = to_ecs(&record.data, record.timestamp);
+
+    Ok(SerializedEvtxRecord {
+        event_record_id: record.event_record_id,
+        timestamp: record.timestamp,
+        data: json_to_string(&ecs, settings, size)?,
+    })
+}
+
+#[allow(clippy::result_large_err)]
+fn json_to_string(
+    value: &serde_json::Value,
+    settings: &ParserSettings,
+    size: u32,
+) -> Result<String, EvtxError> {
+    let mut data = Vec::with_capacity(size as usize * SERIALIZED_SIZE_RATIO);
+    if settings.should_indent() {
+        serde_json::to_writer_pretty(&mut data, value)
+    } else {
+        serde_json::to_writer(&mut data, value)
+    }
+    .map_err(SerializationError::from)?;
+
+    Ok(String::from_utf8(data).map_err(SerializationError::from)?)
+}
+
+#[allow(clippy::result_large_err)]
+fn serialize(
+    record: EvtxRecord,
+    output_format: OutputFormat,
+    sections: Option<&SectionFilter>,
+    settings: &ParserSettings,
+    size: u32,
+) -> Result<SerializedEvtxRecord<String>, EvtxError> {
+    match output_format {
+        OutputFormat::XML => {
+            let mut record = record.into_xml()?;
+            if let Some(sections) = sections {
+                record.data = sections
+                    .apply_xml(record.data)
+                    .map_err(SerializationError::from)?;
+            }
+            Ok(record)
+        }
+        OutputFormat::JSON(shape) => into_shaped_json(record, shape, sections, settings, size),
+        OutputFormat::ECS => into_ecs_json(record, settings, size),
+    }
+}
+
+/// Removes the XML declaration and surrounding whitespace from a serialized record.
+fn strip_to_bare(data: String) -> String {
+    let body = match data.strip_prefix("<?xml") {
+        Some(rest) => rest.find("?>").map_or(rest, |end| &rest[end + 2..]),
+        None => &data,
+    };
+
+    let body = body.trim();
+    if body.len() == data.len() {
+        data
+    } else {
+        body.to_string()
+    }
+}
+
+/// A serialized record, along with the optional fields requested when constructing the parser.
+struct ParsedRecord {
+    record: SerializedEvtxRecord<String>,
+    binary_data: Option<Vec<Vec<u8>>>,
+    /// The timestamp as a FILETIME, when `include_metadata` is set.
+    raw_timestamp: Option<u64>,
+    /// The size of the record in the file, when `include_metadata` is set.
+    size: Option<u32>,
+    /// The codec ANSI strings were decoded with, when `include_metadata` is set.
+    ansi_codec: Option<&'static str>,
+    /// The chunk number and the index of the record within its chunk,
+    /// when `include_metadata` is set.
+    position: Option<(u64, usize)>,
+    /// The SHA-256 of the raw record, as hex, when `content_hash` is set.
+    content_hash: Option<String>,
+    /// The record as MessagePack, when iterating with `records_msgpack()`
+    /// (`record.data` is left empty).
+    encoded: Option<Vec<u8>>,
+    /// The values at each JSON pointer, when iterating with `records_extract()`.
+    extracted: Option<Vec<(String, Option<serde_json::Value>)>>,
+    /// The `<System>` fields, when iterating with `records_tuples()` over some of them
+    /// (or with `include_metadata`, which reports the `Correlation` ones).
+    metadata: Option<Box<RecordMetadata>>,
+    /// The `ActivityID` and `RelatedActivityID` of the record, when `include_metadata` is set.
+    correlation: Option<Box<(Option<String>, Option<String>)>>,
+    /// The GUIDs of the record's templates, when iterating with `records_with_templates()`.
+    template_guids: Option<Vec<String>>,
+}
+
+impl ParsedRecord {
+    /// A record without any of the optional fields.
+    fn new(record: SerializedEvtxRecord<String>) -> Self {
+        ParsedRecord {
+            record,
+            binary_data: None,
+            raw_timestamp: None,
+            size: None,
+            ansi_codec: None,
+            position: None,
+            content_hash: None,
+            encoded: None,
+            extracted: None,
+            metadata: None,
+            correlation: None,
+            template_guids: None,
+        }
+    }
+}
+
+/// Converts a record timestamp back to the FILETIME it was read from.
+fn filetime_from_datetime(timestamp: DateTime<Utc>) -> u64 {
+    const FILETIME_EPOCH_OFFSET: i64 = 11_644_473_600;
+
+    let seconds = (timestamp.timestamp() + FILETIME_EPOCH_OFFSET) as u64;
+    seconds * 10_000_000 + u64::from(timestamp.timestamp_subsec_nanos() / 100)
+}
+
+fn format_timestamp(timestamp: DateTime<Utc>, suffix: bool) -> String {
+    if suffix {
+        format!("{}", timestamp)
+    } else {
+        format!("{}", timestamp.naive_utc())
+    }
+}
+
+/// Encodes a record the same way `record_to_pydict` lays it out, with `data` as a nested map.
+#[allow(clippy::result_large_err)]
+fn record_to_msgpack(
+    parsed: &ParsedRecord,
+    data: &serde_json::Value,
+    timestamp_suffix: bool,
+) -> Result<Vec<u8>, EvtxError> {
+    let record = &parsed.record;
+    let timestamp = format_timestamp(record.timestamp, timestamp_suffix);
+
+    let mut fields = vec![
+        ("event_record_id", Field::UInt(record.event_record_id)),
+        ("timestamp", Field::Str(&timestamp)),
+        ("data", Field::Value(data)),
+    ];
+
+    if let Some(binary_data) = &parsed.binary_data {
+        fields.push(("binary_data", Field::Binaries(binary_data)));
+    }
+
+    if let Some(raw_timestamp) = parsed.raw_timestamp {
+        fields.push(("raw_timestamp", Field::UInt(raw_timestamp)));
+    }
+
+    if let Some(size) = parsed.size {
+        fields.push(("size", Field::UInt(u64::from(size))));
+    }
+
+    if let Some(ansi_codec) = parsed.ansi_codec {
+        fields.push(("ansi_codec", Field::Str(ansi_codec)));
+    }
+
+    if let Some((chunk_number, record_index)) = parsed.position {
+        fields.push(("chunk_number", Field::UInt(chunk_number)));
+        fields.push(("record_index", Field::UInt(record_index as u64)));
+    }
+
+    let null = serde_json::Value::Null;
+    if let Some((activity_id, related_activity_id)) = parsed.correlation.as_deref() {
+        for (key, id) in [
+            ("activity_id", activity_id),
+            ("related_activity_id", related_activity_id),
+        ] {
+            let field = id.as_deref().map_or(Field::Value(&null), Field::Str);
+            fields.push((key, field));
+        }
+    }
+
+    if let Some(content_hash) = &parsed.content_hash {
+        fields.push(("content_hash", Field::Str(content_hash)));
+    }
+
+    Ok(encode_map(&fields)?)
+}
+
+fn record_to_pydict(
+    parsed: ParsedRecord,
+    timestamp_suffix: bool,
+    py: Python<'_>,
+) -> PyResult<Bound<'_, PyDict>> {
+    let record = parsed.record;
+    let pyrecord = PyDict::new(py);
+
+    pyrecord.set_item("event_record_id", record.event_record_id)?;
+    pyrecord.set_item(
+        "timestamp",
+        format_timestamp(record.timestamp, timestamp_suffix),
+    )?;
+    let data_size = record.data.len();
+    pyrecord.set_item("data", record.data)?;
+
+    if let Some(binary_data) = parsed.binary_data {
+        let binary_data = binary_data.iter().map(|b| PyBytes::new(py, b));
+        pyrecord.set_item("binary_data", PyList::new(py, binary_data)?)?;
+    }
+
+    if let Some(raw_timestamp) = parsed.raw_timestamp {
+        pyrecord.set_item("raw_timestamp", raw_timestamp)?;
+    }
+
+    if let Some(size) = parsed.size {
+        pyrecord.set_item("size", size)?;
+        pyrecord.set_item("data_size", data_size)?;
+    }
+
+    if let Some(ansi_codec) = parsed.ansi_codec {
+        pyrecord.set_item("ansi_codec", ansi_codec)?;
+    }
+
+    if let Some((chunk_number, record_index)) = parsed.position {
+        pyrecord.set_item("chunk_number", chunk_number)?;
+        pyrecord.set_item("record_index", record_index)?;
+    }
+
+    if let Some(correlation) = parsed.correlation {
+        let (activity_id, related_activity_id) = *correlation;
+        pyrecord.set_item("activity_id", activity_id)?;
+        pyrecord.set_item("related_activity_id", related_activity_id)?;
+    }
+
+    if let Some(content_hash) = parsed.content_hash {
+        pyrecord.set_item("content_hash", content_hash)?;
+    }
+
+    if let Some(template_guids) = parsed.template_guids {
+        pyrecord.set_item("template_guids", template_guids)?;
+    }
+
+    Ok(pyrecord)
+}
+
+/// An entry of a chunk's records, as yielded by the iterator.
+enum RecordEntry {
+    Record(Result<ParsedRecord, EvtxError>),
+    /// A record which failed to deserialize, reported when `report_record_errors` is set.
+    Error(RecordError),
+}
+
+struct RecordError {
+    chunk_number: u64,
+    chunk_offset: u64,
+    record_index: usize,
+    /// The id of the last record which was deserialized before this one, if any.
+    previous_record_id: Option<u64>,
+    error: String,
+}
+
+fn record_error_to_pydict(error: RecordError, py: Python<'_>) -> PyResult<Bound<'_, PyDict>> {
+    let pyerror = PyDict::new(py);
+
+    pyerror.set_item("chunk_number", error.chunk_number)?;
+    pyerror.set_item("chunk_offset", error.chunk_offset)?;
+    pyerror.set_item("record_index", error.record_index)?;
+    pyerror.set_item("previous_record_id", error.previous_record_id)?;
+    pyerror.set_item("error", error.error)?;
+    Ok(pyerror)
+}
+
+fn record_to_pyobject(
+    r: Result<ParsedRecord, EvtxError>,
+    typed: bool,
+    timestamp_suffix: bool,
+    py: Python,
+) -> PyResult<PyObject> {
+    match r {
+        Ok(ParsedRecord {
+            encoded: Some(encoded),
+            ..
+        }) => Ok(PyBytes::new(py, &encoded).into_any().unbind()),
+        Ok(ParsedRecord {
+            extracted: Some(extracted),
+            ..
+        }) => {
+            let dict = PyDict::new(py);
+            for (pointer, value) in extracted {
+                let value = match value {
+                    Some(value) => json_to_pyobject(py, &value)?,
+                    None => py.None(),
+                };
+                dict.set_item(pointer, value)?;
+            }
+
+            Ok(dict.into_any().unbind())
+        }
+        Ok(r) if typed => Ok(Py::new(py, PyEvtxRecord::from(r))?.into_any()),
+        Ok(r) => match record_to_pydict(r, timestamp_suffix, py) {
+            Ok(dict) => Ok(dict.into_pyobject(py)?.into()),
+            Err(e) => Ok(e.into_pyobject(py)?.into()),
+        },
+        Err(e) => Err(PyEvtxError(e).into()),
+    }
+}
+
+#[pyclass]
+/// A single parsed record, as yielded by `PyEvtxParser.records_typed()`.
+///
+/// The attributes holding the optional keys of record dicts (such as `size`, `binary_data`
+/// or `content_hash`) are `None` unless the matching parser option is set.
+pub struct PyEvtxRecord {
+    #[pyo3(get)]
+    event_record_id: u64,
+    /// The record's timestamp, as a timezone aware `datetime`.
+    #[pyo3(get)]
+    timestamp: DateTime<Utc>,
+    /// The record serialized as XML or JSON.
+    #[pyo3(get)]
+    data: String,
+    binary_data: Option<Vec<Vec<u8>>>,
+    /// The record's timestamp as a FILETIME, when `include_metadata` is set.
+    #[pyo3(get)]
+    raw_timestamp: Option<u64>,
+    /// The size of the record in the file, when `include_metadata` is set.
+    #[pyo3(get)]
+    size: Option<u32>,
+    /// The codec ANSI strings were decoded with, when `include_metadata` is set.
+    #[pyo3(get)]
+    ansi_codec: Option<&'static str>,
+    /// The number of the record's chunk, when `include_metadata` is set.
+    #[pyo3(get)]
+    chunk_number: Option<u64>,
+    /// The index of the record within its chunk, when `include_metadata` is set.
+    #[pyo3(get)]
+    record_index: Option<usize>,
+    /// The `ActivityID` of the record's `<Correlation>`, if any.
+    #[pyo3(get)]
+    activity_id: Option<String>,
+    /// The `RelatedActivityID` of the record's `<Correlation>`, if any.
+    #[pyo3(get)]
+    related_activity_id: Option<String>,
+    /// The SHA-256 of the raw record, as hex, when `content_hash` is set.
+    #[pyo3(get)]
+    content_hash: Option<String>,
+}
+
+impl From<ParsedRecord> for PyEvtxRecord {
+    fn from(parsed: ParsedRecord) -> Self {
+        let record = parsed.record;
+        let (chunk_number, record_index) = parsed.position.unzip();
+        let (activity_id, related_activity_id) = parsed
+            .correlation
+            .map_or((None, None), |correlation| *correlation);
+
+        PyEvtxRecord {
+            event_record_id: record.event_record_id,
+            timestamp: record.timestamp,
+            data: record.data,
+            binary_data: parsed.binary_data,
+            raw_timestamp: parsed.raw_timestamp,
+            size: parsed.size,
+            ansi_codec: parsed.ansi_codec,
+            chunk_number,
+            record_index,
+            activity_id,
+            related_activity_id,
+            content_hash: parsed.content_hash,
+        }
+    }
+}
+
+#[pymethods]
+impl PyEvtxRecord {
+    /// The binary values of the record, when `extract_binary` is set.
+    #[getter]
+    fn binary_data<'py>(&self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyList>>> {
+        self.binary_data
+            .as_ref()
+            .map(|binary_data| PyList::new(py, binary_data.iter().map(|b| PyBytes::new(py, b))))
+            .transpose()
+    }
+
+    /// The size of `data`, when `include_metadata` is set.
+    #[getter]
+    fn data_size(&self) -> Option<usize> {
+        self.size.map(|_| self.data.len())
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "PyEvtxRecord(event_record_id={}, timestamp={})",
+            self.event_record_id, self.timestamp
+        )
+    }
+}
+
+#[pyclass]
 pub struct PyRecordsIterator {
-    inner: IntoIterChunks<Box<dyn ReadSeek>>,
-    records_iter: IntoIter<Result<SerializedEvtxRecord<String>, EvtxError>>,
+    inner: EvtxParser<Box<dyn ReadSeek>>,
+    following: IntoIter<EvtxParser<Box<dyn ReadSeek>>>,
+    /// The chunks of `inner` which are yet to be parsed.
+    chunks: Range<u64>,
+    /// The records of the chunk being iterated over.
+    records: Option<ChunkRecords>,
+    /// The number of the chunk being iterated over.
+    chunk_id: u64,
+    first_chunk_offset: u64,
+    read_failure: ReadFailure,
     settings: Arc<ParserSettings>,
     output_format: OutputFormat,
+    bare: bool,
+    checksum_policy: ChecksumPolicy,
+    extract_binary: bool,
+    report_record_errors: bool,
+    max_depth: Option<usize>,
+    include_metadata: bool,
+    max_records: Option<u64>,
+    timestamp_suffix: bool,
+    /// Settings with each of the `ansi_codec_fallbacks`.
+    fallback_settings: Vec<Arc<ParserSettings>>,
+    xml_invalid_chars: InvalidXmlChars,
+    binary_encoding: BinaryEncoding,
+    on_chunk_error: Option<PyObject>,
+    content_hash: bool,
+    skip_empty: bool,
+    /// The number of records read so far, including the ones which were filtered out.
+    records_parsed: u64,
+    /// The id of the last record which was deserialized, for context in record errors.
+    previous_record_id: Option<u64>,
+    checksum_mismatches: Vec<u64>,
+    record_errors: u64,
+    partial_final_chunk: bool,
+    text_filter: Option<TextFilter>,
+    records_yielded: u64,
+    typed: bool,
+    /// Whether records are yielded as MessagePack `bytes`, see `records_msgpack()`.
+    msgpack: bool,
+    /// Whether records are rendered as their text content, see `records_text()`.
+    text: bool,
+    /// Whether only the records which fail are yielded, see `records_failed()`.
+    failed_only: bool,
+    /// Whether only the records holding template instances are yielded,
+    /// see `records_with_templates()`.
+    with_templates: bool,
+    /// The sections to keep in JSON records, see `records_json()`.
+    sections: Option<SectionFilter>,
+    /// JSON pointers to extract from each record, see `records_extract()`.
+    pointers: Option<Vec<String>>,
+    /// The number of records per batch, see `records_arrow_batches()`.
+    batch_size: Option<usize>,
+    /// The fields of the tuples to yield, see `records_tuples()`.
+    tuple_fields: Option<Vec<TupleField>>,
+    /// Called with the data of each record, see `records()`.
+    transform: Option<PyObject>,
+    slice: RecordsSlice,
+    /// Only records with ids in this range are yielded, when set.
+    record_ids: Option<RangeInclusive<u64>>,
+    provider_filter: Option<ProviderFilter>,
+    /// Only records matching this expression are yielded, when set (see `records()`).
+    expression: Option<FilterExpression>,
+    /// Only records with one of these keywords are yielded, when set (see `records()`).
+    keywords_mask: Option<u64>,
+    /// The index of the file being iterated over, when `multi_file` is set.
+    file_index: u32,
+    /// The first record id of the chunk being iterated over, as stored in its header.
+    chunk_first_record_id: u64,
+    /// The position of the last record yielded.
+    checkpoint: Option<Checkpoint>,
+    /// The checkpoint iteration was resumed from, the records up to which are skipped.
+    resumed: Option<Checkpoint>,
+}
+
+/// The part of the remaining records to yield, as set by slicing the iterator.
+#[derive(Debug, Clone, Copy)]
+struct RecordsSlice {
+    /// Records to drop before yielding the next one.
+    skip: u64,
+    step: u64,
+    /// Records left to yield, `None` when unbounded.
+    remaining: Option<u64>,
+}
+
+impl Default for RecordsSlice {
+    fn default() -> Self {
+        RecordsSlice {
+            skip: 0,
+            step: 1,
+            remaining: None,
+        }
+    }
+}
+
+impl RecordsSlice {
+    /// Slices the records described by `self`, the same way a Python list would be sliced.
+    fn slice(self, start: u64, stop: Option<u64>, step: u64) -> RecordsSlice {
+        let stop = match (stop, self.remaining) {
+            (Some(stop), Some(remaining)) => Some(stop.min(remaining)),
+            (stop, remaining) => stop.or(remaining),
+        };
+
+        RecordsSlice {
+            skip: self.skip + self.step * start,
+            step: self.step * step,
+            remaining: stop.map(|stop| stop.saturating_sub(start).div_ceil(step)),
+        }
+    }
+
+    fn is_exhausted(&self) -> bool {
+        self.remaining == Some(0)
+    }
+
+    /// Called for every record, returns whether it should be yielded.
+    fn accepts_next(&mut self) -> bool {
+        if self.skip > 0 {
+            self.skip -= 1;
+            return false;
+        }
+
+        self.skip = self.step - 1;
+        if let Some(remaining) = self.remaining.as_mut() {
+            *remaining -= 1;
+        }
+
+        true
+    }
+}
+
+/// Provider names and GUIDs, normalized by `ProviderFilter::normalize`.
+struct ProviderFilter {
+    providers: Vec<String>,
+}
+
+impl ProviderFilter {
+    fn new(providers: Vec<String>) -> Self {
+        ProviderFilter {
+            providers: providers.iter().map(|p| Self::normalize(p)).collect(),
+        }
+    }
+
+    fn normalize(provider: &str) -> String {
+        provider
+            .trim_start_matches('{')
+            .trim_end_matches('}')
+            .to_lowercase()
+    }
+
+    fn accepts_metadata(&self, metadata: &RecordMetadata) -> bool {
+        [&metadata.provider_name, &metadata.provider_guid]
+            .iter()
+            .copied()
+            .flatten()
+            .any(|provider| self.providers.contains(&Self::normalize(provider)))
+    }
+}
+
+struct TextFilter {
+    regex: Regex,
+    invert: bool,
 }
 
-impl PyRecordsIterator {
+impl TextFilter {
+    fn accepts(&self, entry: &RecordEntry) -> bool {
+        match entry {
+            RecordEntry::Record(Ok(parsed)) => {
+                self.regex.is_match(&parsed.record.data) != self.invert
+            }
+            // Errors are always passed on to the caller.
+            RecordEntry::Record(Err(_)) | RecordEntry::Error(_) => true,
+        }
+    }
+}
+
+impl PyRecordsIterator {
+    /// Serializes a record, reusing its `<System>` fields if a filter has already read them.
+    #[allow(clippy::result_large_err)]
+    fn serialize_record(
+        &self,
+        mut record: EvtxRecord,
+        record_index: usize,
+        data: &[u8],
+        metadata: Option<RecordMetadata>,
+    ) -> Result<ParsedRecord, EvtxError> {
+        let size = data.len() as u32;
+        let content_hash = self
+            .content_hash
+            .then(|| format!("{:x}", Sha256::digest(data)));
+        let binary_data = self.extract_binary.then(|| binary_values(&record));
+        let raw_timestamp = self
+            .include_metadata
+            .then(|| filetime_from_datetime(record.timestamp));
+        let ansi_codec = self
+            .include_metadata
+            .then(|| record.settings.get_ansi_codec().name());
+        let template_guids = self.with_templates.then(|| {
+            template_instances(&record.tokens)
+                .iter()
+                .filter_map(|instance| {
+                    template_guid(record.chunk.data, instance.template_def_offset)
+                })
+                .collect()
+        });
+        let needs_metadata = self.include_metadata
+            || self
+                .tuple_fields
+                .as_ref()
+                .is_some_and(|fields| fields.iter().any(|field| field.is_metadata()));
+        let metadata = match metadata {
+            Some(metadata) if needs_metadata => Some(Box::new(metadata)),
+            None if needs_metadata => Some(Box::new(RecordMetadata::from_record(&record)?)),
+            _ => None,
+        };
+        let correlation = metadata
+            .as_ref()
+            .filter(|_| self.include_metadata)
+            .map(|metadata| {
+                Box::new((
+                    metadata.activity_id.clone(),
+                    metadata.related_activity_id.clone(),
+                ))
+            });
+
+        let xml = !(self.msgpack || self.text || self.pointers.is_some())
+            && self.output_format == OutputFormat::XML;
+        let cdata = self.binary_encoding.apply(&mut record, xml);
+
+        let (record, value) = if self.msgpack || self.text || self.pointers.is_some() {
+            let mut record = record.into_json_value()?;
+            if let Some(sections) = &self.sections {
+                sections.apply(&mut record.data);
+            }
+            if let OutputFormat::JSON(shape) = self.output_format {
+                shape.apply(&mut record.data);
+            }
+
+            let serialized = SerializedEvtxRecord {
+                event_record_id: record.event_record_id,
+                timestamp: record.timestamp,
+                data: String::new(),
+            };
+
+            (serialized, Some(record.data))
+        } else {
+            let mut record = serialize(
+                record,
+                self.output_format,
+                self.sections.as_ref(),
+                &self.settings,
+                size,
+            )?;
+            if self.bare {
+                record.data = strip_to_bare(record.data);
+            }
+
+            if self.output_format == OutputFormat::XML {
+                if cdata {
+                    record.data = BinaryEncoding::finish(record.data);
+                }
+                record.data = self.xml_invalid_chars.apply(record.data);
+            }
+
+            (record, None)
+        };
+
+        let mut parsed = ParsedRecord {
+            binary_data,
+            raw_timestamp,
+            size: self.include_metadata.then_some(size),
+            ansi_codec,
+            position: self
+                .include_metadata
+                .then_some((self.chunk_id, record_index)),
+            content_hash,
+            metadata,
+            correlation,
+            template_guids,
+            ..ParsedRecord::new(record)
+        };
+
+        if let Some(value) = value {
+            if self.msgpack {
+                parsed.encoded = Some(record_to_msgpack(&parsed, &value, self.timestamp_suffix)?);
+            }
+
+            if self.text {
+                parsed.record.data = text_content(&value);
+            }
+
+            if let Some(pointers) = &self.pointers {
+                let extracted = pointers
+                    .iter()
+                    .map(|pointer| (pointer.clone(), value.pointer(pointer).cloned()))
+                    .collect();
+                parsed.extracted = Some(extracted);
+            }
+        }
+
+        Ok(parsed)
+    }
+
+    /// Moves to the position of `checkpoint`, after checking that it was taken on the same file.
+    fn resume_from(&mut self, checkpoint: Checkpoint) -> PyResult<()> {
+        let mismatch = || PyErr::new::<PyValueError, _>("The checkpoint does not match this file");
+
+        for _ in 0..checkpoint.file_index {
+            self.inner = self.following.next().ok_or_else(mismatch)?;
+            self.chunks = 0..u64::MAX;
+        }
+
+        if !self.chunks.contains(&checkpoint.chunk_number) {
+            return Err(mismatch());
+        }
+
+        match self.inner.find_next_chunk(checkpoint.chunk_number) {
+            Some((Ok(chunk), chunk_number))
+                if chunk_number == checkpoint.chunk_number
+                    && chunk.header.first_event_record_id == checkpoint.chunk_first_record_id => {}
+            _ => return Err(mismatch()),
+        }
+
+        self.chunks.start = checkpoint.chunk_number;
+        self.file_index = checkpoint.file_index;
+        self.checkpoint = Some(checkpoint);
+        self.resumed = Some(checkpoint);
+
+        Ok(())
+    }
+
+    /// Decides what to do with a chunk which failed to parse: raises without `on_chunk_error`,
+    /// otherwise returns whether to skip it, after stopping the iteration if not.
+    fn skip_chunk_error(&mut self, chunk_id: u64, error: EvtxError) -> PyResult<bool> {
+        let callback = match &self.on_chunk_error {
+            Some(callback) => callback,
+            None => return Err(read_error(&self.read_failure, error)),
+        };
+
+        // The same message as the exception raised without a callback.
+        let message = match (self.read_failure.take(), &error) {
+            (Some(failure), _) => failure,
+            (None, EvtxError::FailedToParseChunk { source, .. }) => source.to_string(),
+            (None, error) => error.to_string(),
+        };
+
+        let skip = Python::with_gil(|py| callback.call1(py, (chunk_id, message))?.is_truthy(py))?;
+
+        if !skip {
+            self.chunks = 0..0;
+            self.following = Vec::new().into_iter();
+        }
+
+        Ok(skip)
+    }
+
+    fn check_max_records(&self) -> PyResult<()> {
+        match self.max_records {
+            Some(max_records) if self.records_parsed > max_records => {
+                Err(PyErr::new::<PyRuntimeError, _>((
+                    format!(
+                        "Parsed {} records, more than `max_records` ({})",
+                        self.records_parsed, max_records
+                    ),
+                    max_records,
+                    self.records_parsed,
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Sets the `providers`, `where` and `keywords_mask` filters of `records()` and `records_json()`.
+    fn set_filters(
+        &mut self,
+        providers: Option<Vec<String>>,
+        r#where: Option<&str>,
+        keywords_mask: Option<u64>,
+    ) -> PyResult<()> {
+        self.expression = r#where.map(FilterExpression::parse).transpose()?;
+        self.keywords_mask = keywords_mask;
+        self.provider_filter = providers.map(ProviderFilter::new);
+
+        Ok(())
+    }
+
+    /// Whether a record passes the `providers`, `where` and `keywords_mask` filters of `records()`.
+    fn accepts_metadata(&self, metadata: &RecordMetadata, event_record_id: u64) -> bool {
+        let matches_provider = self
+            .provider_filter
+            .as_ref()
+            .is_none_or(|filter| filter.accepts_metadata(metadata));
+        let matches_expression = self
+            .expression
+            .as_ref()
+            .is_none_or(|expression| expression.matches(metadata, event_record_id));
+        let matches_keywords = self
+            .keywords_mask
+            .is_none_or(|mask| metadata.keywords.unwrap_or(0) & mask != 0);
+
+        matches_provider && matches_expression && matches_keywords
+    }
+
+    /// Turns a record of the current chunk into the entry to yield, or `None` if it is filtered out.
+    fn record_entry(
+        &mut self,
+        record: Result<EvtxRecord, EvtxError>,
+        record_index: usize,
+        data: &[u8],
+    ) -> Option<RecordEntry> {
+        let previous_record_id = self.previous_record_id;
+        let mut record = record.map_err(|error| error.to_string());
+
+        if let Ok(evtx_record) = &record {
+            self.previous_record_id = Some(evtx_record.event_record_id);
+
+            if let Some(resumed) = &self.resumed {
+                if resumed.file_index == self.file_index
+                    && resumed.chunk_number == self.chunk_id
+                    && evtx_record.event_record_id <= resumed.record_id
+                {
+                    return None;
+                }
+            }
+
+            if let Some(ids) = &self.record_ids {
+                if !ids.contains(&evtx_record.event_record_id) {
+                    return None;
+                }
+            }
+
+            // Checked before any filter which reads the record's tokens,
+            // as those walk (or render) the whole record.
+            if let Some(max_depth) = self.max_depth {
+                if exceeds_depth(evtx_record, max_depth) {
+                    record = Err(format!(
+                        "Record {} is nested deeper than `max_depth` ({}), or expands to too many tokens",
+                        evtx_record.event_record_id, max_depth
+                    ));
+                }
+            }
+        }
+
+        // The `<System>` fields, when a filter has read them, to be reused by `serialize_record`.
+        let mut metadata = None;
+
+        if let Ok(record) = &record {
+            if self.provider_filter.is_some()
+                || self.expression.is_some()
+                || self.keywords_mask.is_some()
+            {
+                // Records which can't be read are passed on, to fail when being serialized.
+                if let Ok(read) = RecordMetadata::from_record(record) {
+                    if !self.accepts_metadata(&read, record.event_record_id) {
+                        return None;
+                    }
+                    metadata = Some(read);
+                }
+            }
+
+            if self.with_templates && template_instances(&record.tokens).is_empty() {
+                return None;
+            }
+
+            if self.skip_empty {
+                // Records which can't be read are passed on, to fail when being serialized.
+                if let Ok(false) = record_has_payload(record) {
+                    return None;
+                }
+            }
+        }
+
+        let entry = match record {
+            Ok(record) => {
+                let record_id = record.event_record_id;
+
+                match self.serialize_record(record, record_index, data, metadata) {
+                    Ok(parsed)
+                        if self.xml_invalid_chars == InvalidXmlChars::Error
+                            && self.output_format == OutputFormat::XML
+                            && contains_invalid_chars(&parsed.record.data) =>
+                    {
+                        Err(format!(
+                            "Record {} contains characters which are not allowed in XML",
+                            record_id
+                        ))
+                    }
+                    Ok(_) if self.failed_only => return None,
+                    Err(e) if self.failed_only => Err(e.to_string()),
+                    parsed => Ok(RecordEntry::Record(parsed)),
+                }
+            }
+            Err(error) => Err(error),
+        };
+
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(error) => {
+                self.record_errors += 1;
+                if !self.report_record_errors && !self.failed_only {
+                    return None;
+                }
+
+                RecordEntry::Error(RecordError {
+                    chunk_number: self.chunk_id,
+                    chunk_offset: chunk_offset(self.chunk_id, self.first_chunk_offset),
+                    record_index,
+                    previous_record_id,
+                    error,
+                })
+            }
+        };
+
+        match &self.text_filter {
+            Some(filter) if !filter.accepts(&entry) => None,
+            _ => Some(entry),
+        }
+    }
+
     fn next(&mut self) -> PyResult<Option<PyObject>> {
-        let mut chunk_id = 0;
+        if let Some(batch_size) = self.batch_size {
+            return self.next_batch(batch_size);
+        }
+
+        let entry = match self.next_entry()? {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+
+        let record = Python::with_gil(|py| match entry {
+            RecordEntry::Record(record) => match &self.tuple_fields {
+                Some(fields) => {
+                    let record = record.map_err(PyEvtxError)?;
+                    Ok(record_to_pytuple(py, record, fields, self.timestamp_suffix)?.into())
+                }
+                None => {
+                    let record = record_to_pyobject(record, self.typed, self.timestamp_suffix, py)?;
+                    self.apply_transform(py, &record)?;
+                    Ok::<_, PyErr>(record)
+                }
+            },
+            RecordEntry::Error(error) => Ok(record_error_to_pydict(error, py)?.into()),
+        })?;
+        self.records_yielded += 1;
+
+        Ok(Some(record))
+    }
+
+    /// Writes the remaining records as a JSON array, see `PyEvtxParser.to_json_array()`.
+    fn write_json_array(&mut self, writer: &mut impl Write) -> PyResult<()> {
+        writer.write_all(b"[")?;
+
+        while let Some(entry) = self.next_entry()? {
+            let parsed = match entry {
+                RecordEntry::Record(record) => record.map_err(PyEvtxError)?,
+                RecordEntry::Error(_) => continue,
+            };
+
+            let separator: &[u8] = if self.records_yielded == 0 {
+                b"\n"
+            } else {
+                b",\n"
+            };
+            writer.write_all(separator)?;
+            writer.write_all(parsed.record.data.as_bytes())?;
+            self.records_yielded += 1;
+        }
+
+        writer.write_all(b"\n]")?;
+        Ok(())
+    }
+
+    /// Replaces the `data` of a record dict with the result of `transform`, when set.
+    fn apply_transform(&self, py: Python, record: &PyObject) -> PyResult<()> {
+        let transform = match &self.transform {
+            Some(transform) => transform,
+            None => return Ok(()),
+        };
+
+        if let Ok(dict) = record.downcast_bound::<PyDict>(py) {
+            if let Some(data) = dict.get_item("data")? {
+                dict.set_item("data", transform.call1(py, (data,))?)?;
+            }
+        }
 
+        Ok(())
+    }
+
+    /// Collects up to `batch_size` records into a dict of columns, see `records_arrow_batches()`.
+    fn next_batch(&mut self, batch_size: usize) -> PyResult<Option<PyObject>> {
+        let pointers = self.pointers.clone().unwrap_or_default();
+
+        let mut record_ids = Vec::with_capacity(batch_size);
+        let mut timestamps = Vec::with_capacity(batch_size);
+        let mut columns = vec![Vec::with_capacity(batch_size); pointers.len()];
+
+        while record_ids.len() < batch_size {
+            let parsed = match self.next_entry()? {
+                Some(RecordEntry::Record(record)) => record.map_err(PyEvtxError)?,
+                // Failed records are counted in `record_errors`, but have no place in columns.
+                Some(RecordEntry::Error(_)) => continue,
+                None => break,
+            };
+
+            record_ids.push(parsed.record.event_record_id);
+            timestamps.push(parsed.record.timestamp);
+            for (column, (_, value)) in columns.iter_mut().zip(parsed.extracted.unwrap_or_default())
+            {
+                column.push(value);
+            }
+            self.records_yielded += 1;
+        }
+
+        if record_ids.is_empty() {
+            return Ok(None);
+        }
+
+        Python::with_gil(|py| {
+            let batch = PyDict::new(py);
+            batch.set_item("event_record_id", record_ids)?;
+            batch.set_item("timestamp", timestamps)?;
+            for (pointer, values) in pointers.iter().zip(&columns) {
+                let column_type = ColumnType::infer(values);
+                batch.set_item(pointer, column_to_pylist(py, values, column_type)?)?;
+            }
+
+            Ok(Some(batch.into_any().unbind()))
+        })
+    }
+
+    /// Advances to the next entry to yield, without converting it to a Python object.
+    fn next_entry(&mut self) -> PyResult<Option<RecordEntry>> {
         loop {
-            if let Some(record) = self.records_iter.next() {
-                let record = Python::with_gil(|py| record_to_pyobject(record, py).map(Some));
+            if self.slice.is_exhausted() {
+                return Ok(None);
+            }
+
+            if let Some(mut records) = self.records.take() {
+                let entry = match records
+                    .next_with(|record, index, data| self.record_entry(record, index, data))
+                {
+                    Some(entry) => {
+                        self.records = Some(records);
+                        self.records_parsed += 1;
+                        self.check_max_records()?;
+                        entry
+                    }
+                    // Done with this chunk.
+                    None => {
+                        self.partial_final_chunk |= records.truncated();
+                        continue;
+                    }
+                };
 
-                return record;
+                let entry = match entry {
+                    Some(entry) if self.slice.accepts_next() => entry,
+                    _ => continue,
+                };
+
+                if let RecordEntry::Record(Ok(parsed)) = &entry {
+                    self.checkpoint = Some(Checkpoint {
+                        file_index: self.file_index,
+                        chunk_number: self.chunk_id,
+                        chunk_first_record_id: self.chunk_first_record_id,
+                        record_id: parsed.record.event_record_id,
+                    });
+                }
+
+                return Ok(Some(entry));
             }
 
-            let chunk = self.inner.next();
-            chunk_id += 1;
+            let chunk = if self.chunks.is_empty() {
+                None
+            } else {
+                self.inner
+                    .find_next_chunk(self.chunks.start)
+                    .filter(|(_, chunk_id)| *chunk_id < self.chunks.end)
+            };
 
             match chunk {
-                None => return Ok(None),
-                Some(chunk_result) => match chunk_result {
-                    Err(e) => {
-                        return Err(PyEvtxError(e).into());
+                None => match self.following.next() {
+                    Some(parser) => {
+                        self.inner = parser;
+                        self.chunks = 0..u64::MAX;
+                        self.file_index += 1;
                     }
-                    Ok(mut chunk) => {
-                        let parsed_chunk = chunk.parse(self.settings.clone());
-
-                        match parsed_chunk {
-                            Err(e) => {
-                                return Err(PyEvtxError(EvtxError::FailedToParseChunk {
-                                    chunk_id,
-                                    source: e,
-                                })
-                                .into());
+                    None => return Ok(None),
+                },
+                Some((chunk_result, chunk_id)) => {
+                    self.chunks.start = chunk_id + 1;
+
+                    match chunk_result {
+                        Err(e) => {
+                            if self.skip_chunk_error(chunk_id, e)? {
+                                continue;
+                            }
+                            return Ok(None);
+                        }
+                        Ok(chunk) => {
+                            if let Some(ids) = &self.record_ids {
+                                let header = &chunk.header;
+                                if header.last_event_record_id < *ids.start()
+                                    || header.first_event_record_id > *ids.end()
+                                {
+                                    continue;
+                                }
                             }
-                            Ok(mut chunk) => {
-                                let records: Vec<_> = match self.output_format {
-                                    OutputFormat::XML => chunk
-                                        .iter()
-                                        .filter_map(|r| r.ok())
-                                        .map(|r| r.into_xml())
-                                        .collect(),
-                                    OutputFormat::JSON => chunk
-                                        .iter()
-                                        .filter_map(|r| r.ok())
-                                        .map(|r| r.into_json())
-                                        .collect(),
-                                };
-
-                                self.records_iter = records.into_iter();
+
+                            if self.checksum_policy == ChecksumPolicy::Warn
+                                && !chunk.validate_checksum()
+                            {
+                                self.checksum_mismatches.push(chunk_id);
+                                Python::with_gil(|py| {
+                                    PyErr::warn(
+                                        py,
+                                        &py.get_type::<PyRuntimeWarning>(),
+                                        &CString::new(format!(
+                                            "Checksum mismatch in chunk {}",
+                                            chunk_id
+                                        ))?,
+                                        // Attributes the warning to the code iterating over the records.
+                                        1,
+                                    )
+                                })?;
+                            }
+
+                            self.chunk_first_record_id = chunk.header.first_event_record_id;
+                            let records = match ChunkRecords::new(chunk, self.settings.clone()) {
+                                Ok(records) => records,
+                                Err(source) => {
+                                    let e = EvtxError::FailedToParseChunk { chunk_id, source };
+                                    if self.skip_chunk_error(chunk_id, e)? {
+                                        continue;
+                                    }
+                                    return Ok(None);
+                                }
                             }
+                            .with_fallback_settings(self.fallback_settings.clone());
+
+                            self.chunk_id = chunk_id;
+                            self.records = Some(records);
                         }
                     }
-                },
+                }
             }
         }
     }
@@ -348,6 +3550,409 @@ impl PyRecordsIterator {
     fn __next__(mut slf: PyRefMut<'_, Self>) -> PyResult<Option<PyObject>> {
         slf.next()
     }
+
+    /// Slices the remaining records, for example `parser.records()[10:20]`.
+    ///
+    /// Records are not randomly accessible, so this skips over the records preceding `start`
+    /// (which is `O(start)`), and the iterator itself is returned rather than a copy.
+    /// Negative indices are not supported, as the number of records is not known in advance.
+    fn __getitem__<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        key: &Bound<'py, PySlice>,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        let index = |name: &str| -> PyResult<Option<u64>> {
+            let value: Option<i64> = key.getattr(name)?.extract()?;
+            value
+                .map(|value| {
+                    u64::try_from(value).map_err(|_| {
+                        PyErr::new::<PyValueError, _>(
+                            "Negative indices are not supported when slicing records",
+                        )
+                    })
+                })
+                .transpose()
+        };
+
+        let start = index("start")?.unwrap_or(0);
+        let stop = index("stop")?;
+        let step = index("step")?.unwrap_or(1);
+
+        if step == 0 {
+            return Err(PyErr::new::<PyValueError, _>("slice step cannot be zero"));
+        }
+
+        slf.slice = slf.slice.slice(start, stop, step);
+        Ok(slf)
+    }
+
+    /// checkpoint(self, /)
+    /// --
+    ///
+    /// Returns a token (as `bytes`) holding the position of the last record yielded,
+    /// from which a new iterator over the same file can resume
+    /// (see the `resume_from` argument of `PyEvtxParser.records()`),
+    /// or `None` if no record was yielded yet.
+    ///
+    /// This allows long running ingestion to pick up where it left off after a crash,
+    /// without yielding the records it already handled again.
+    fn checkpoint<'py>(&self, py: Python<'py>) -> Option<Bound<'py, PyBytes>> {
+        self.checkpoint
+            .map(|checkpoint| PyBytes::new(py, &checkpoint.to_bytes()))
+    }
+
+    /// The number of records yielded so far.
+    #[getter]
+    fn records_yielded(&self) -> u64 {
+        self.records_yielded
+    }
+
+    /// The numbers of the chunks found so far to have mismatching checksums,
+    /// when iterating with `checksum_policy='warn'`.
+    #[getter]
+    fn checksum_mismatches(&self) -> Vec<u64> {
+        self.checksum_mismatches.clone()
+    }
+
+    /// The number of records so far which failed to deserialize.
+    #[getter]
+    fn record_errors(&self) -> u64 {
+        self.record_errors
+    }
+
+    /// Whether iteration stopped at an incomplete record in a chunk flagged as dirty,
+    /// as is the case for the active chunk of a file copied from a running system.
+    /// The records preceding it are yielded, and the incomplete one is not reported as an error.
+    #[getter]
+    fn partial_final_chunk(&self) -> bool {
+        self.partial_final_chunk
+    }
+}
+
+/// build_info()
+/// --
+///
+/// Returns a dict describing how this module was built, useful when filing bug reports.
+///
+/// Keys:
+///     `version`: the version of these bindings.
+///     `evtx_version`: the version of the underlying `evtx` crate.
+///     `pyo3_version`: the version of PyO3.
+///     `wevt_templates`: whether WEVT template support is compiled in.
+#[pyfunction]
+fn build_info(py: Python<'_>) -> PyResult<Bound<'_, PyDict>> {
+    let info = PyDict::new(py);
+
+    info.set_item("version", env!("CARGO_PKG_VERSION"))?;
+    info.set_item("evtx_version", env!("EVTX_RS_VERSION"))?;
+    info.set_item("pyo3_version", env!("PYO3_VERSION"))?;
+    info.set_item("wevt_templates", WEVT_TEMPLATES)?;
+    Ok(info)
+}
+
+/// has_wevt_support()
+/// --
+///
+/// Returns whether WEVT template support is compiled in.
+#[pyfunction]
+fn has_wevt_support() -> bool {
+    WEVT_TEMPLATES
+}
+
+/// parse_chunk_bytes(data, output_format='xml', ansi_codec='windows-1252', /)
+/// --
+///
+/// Parses the raw bytes of a single chunk, and returns a list of its records
+/// (as dicts, like the ones yielded by `PyEvtxParser.records()`).
+///
+/// This is the primitive the records iterators are built upon. Along with reading
+/// chunks out of the file directly (at `4096 + 65536 * chunk_number`),
+/// it allows chunks to be distributed between worker processes and parsed independently.
+///
+/// Raises `ValueError` if `data` is not a chunk, and `RuntimeError`
+/// if one of its records fails to deserialize.
+///
+/// Args:
+///     `data` (bytes): the 65536 bytes of the chunk, starting with its header.
+///
+///     `output_format` (str, optional): `xml` (the default) or `json`.
+///
+///     `ansi_codec` (str, optional): the encoding of ansi strings, as in `PyEvtxParser`.
+#[pyfunction]
+#[pyo3(signature = (data, output_format="xml", ansi_codec=None))]
+#[allow(clippy::result_large_err)]
+fn parse_chunk_bytes<'py>(
+    py: Python<'py>,
+    data: &[u8],
+    output_format: &str,
+    ansi_codec: Option<String>,
+) -> PyResult<Bound<'py, PyList>> {
+    let output_format = OutputFormat::from_name(output_format)?;
+    let settings = Arc::new(ParserSettings::new().ansi_codec(ansi_codec_setting(ansi_codec)?));
+
+    if data.len() as u64 != EVTX_CHUNK_SIZE {
+        return Err(PyErr::new::<PyValueError, _>(format!(
+            "not a valid EVTX chunk: expected {} bytes, found {}",
+            EVTX_CHUNK_SIZE,
+            data.len()
+        )));
+    }
+
+    let chunk = EvtxChunkData::new(data.to_vec(), false)
+        .map_err(|e| PyErr::new::<PyValueError, _>(format!("not a valid EVTX chunk: {}", e)))?;
+    let mut records = ChunkRecords::new(chunk, Arc::clone(&settings)).map_err(|source| {
+        PyEvtxError(EvtxError::FailedToParseChunk {
+            chunk_id: 0,
+            source,
+        })
+    })?;
+
+    let list = PyList::empty(py);
+    while let Some(record) = records.next_with(|record, _, data| {
+        serialize(record?, output_format, None, &settings, data.len() as u32)
+    }) {
+        let record = ParsedRecord::new(record.map_err(PyEvtxError)?);
+        list.append(record_to_pydict(record, true, py)?)?;
+    }
+
+    Ok(list)
+}
+
+/// benchmark(path, iterations=1, output_format='xml', number_of_threads=0, /)
+/// --
+///
+/// Parses the file at `path` `iterations` times, serializing every record,
+/// and returns a dict of timing statistics. This is meant for comparing settings
+/// (such as `number_of_threads`) on a given file, and for spotting performance regressions.
+///
+/// The GIL is released while parsing, and the records are not converted to Python objects,
+/// so this measures the parser alone. Each run is timed as a whole, from outside of `evtx`'s
+/// pipeline (which parses chunks in parallel): there is no breakdown by phase
+/// (such as reading chunks, deserializing records and rendering them).
+///
+/// Keys:
+///     `iterations`, `chunks`, `records` and `record_errors` (counted in a single iteration,
+///     the last one), `number_of_threads`, `total_seconds`, and `records_per_second` and
+///     `chunks_per_second` (over all the iterations, or 0.0 if no time was measured).
+///
+/// Args:
+///     `path` (str or os.PathLike): the file to parse.
+///
+///     `iterations` (int, optional): how many times to parse the file.
+///
+///     `output_format` (str, optional): `xml` (the default) or `json`.
+///
+///     `number_of_threads` (int, optional): as in `PyEvtxParser`.
+#[pyfunction]
+#[pyo3(signature = (path, iterations=1, output_format="xml", number_of_threads=None))]
+#[allow(clippy::result_large_err)]
+fn benchmark<'py>(
+    py: Python<'py>,
+    path: PathBuf,
+    iterations: u32,
+    output_format: &str,
+    number_of_threads: Option<usize>,
+) -> PyResult<Bound<'py, PyDict>> {
+    let output_format = OutputFormat::from_name(output_format)?;
+    if iterations == 0 {
+        return Err(PyErr::new::<PyValueError, _>(
+            "`iterations` must be at least 1",
+        ));
+    }
+
+    let settings = match number_of_threads {
+        Some(number) => ParserSettings::new().num_threads(number),
+        None => ParserSettings::new(),
+    };
+    let number_of_threads = resolved_num_threads(&settings);
+
+    let (chunks, records, record_errors, elapsed) = py.allow_threads(|| {
+        let open = || {
+            EvtxParser::from_path(&path)
+                .map(|parser| parser.with_configuration(settings.clone()))
+                .map_err(PyEvtxError)
+        };
+
+        let chunks = open()?.chunks().count() as u64;
+        let (mut records, mut record_errors) = (0_u64, 0_u64);
+
+        let start = Instant::now();
+        for _ in 0..iterations {
+            let mut parser = open()?;
+            let serialized = parser.serialized_records(|record| {
+                record.and_then(|record| serialize(record, output_format, None, &settings, 0))
+            });
+
+            (records, record_errors) = (0, 0);
+            for record in serialized {
+                match record {
+                    Ok(_) => records += 1,
+                    Err(_) => record_errors += 1,
+                }
+            }
+        }
+
+        Ok::<_, PyErr>((chunks, records, record_errors, start.elapsed()))
+    })?;
+
+    let total_seconds = elapsed.as_secs_f64();
+    // A timer too coarse for a tiny file can measure nothing, which has no meaningful rate.
+    let rate = |count: u64| {
+        if elapsed.is_zero() {
+            0.0
+        } else {
+            (count * u64::from(iterations)) as f64 / total_seconds
+        }
+    };
+
+    let stats = PyDict::new(py);
+    stats.set_item("iterations", iterations)?;
+    stats.set_item("chunks", chunks)?;
+    stats.set_item("records", records)?;
+    stats.set_item("record_errors", record_errors)?;
+    stats.set_item("number_of_threads", number_of_threads)?;
+    stats.set_item("total_seconds", total_seconds)?;
+    stats.set_item("records_per_second", rate(records))?;
+    stats.set_item("chunks_per_second", rate(chunks))?;
+    Ok(stats)
+}
+
+/// Puts `item` on `queue`, waiting for room as long as the stream is not stopped.
+/// Returns whether the item was put, or the error raised by `put` (other than `full`, `queue.Full`).
+fn put_until_stopped(
+    queue: &PyObject,
+    full: &PyObject,
+    item: PyObject,
+    stopped: &AtomicBool,
+) -> PyResult<bool> {
+    // `put` can't be interrupted, so it times out to let `stopped` be checked.
+    const PUT_TIMEOUT_SECONDS: f64 = 0.1;
+
+    while !stopped.load(Ordering::Relaxed) {
+        let result = Python::with_gil(|py| {
+            match queue.call_method1(py, "put", (item.clone_ref(py), true, PUT_TIMEOUT_SECONDS)) {
+                Ok(_) => Ok(true),
+                Err(e) if e.is_instance(py, full.bind(py)) => Ok(false),
+                Err(e) => Err(e),
+            }
+        });
+
+        if result? {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+#[pyclass]
+/// A handle on the background thread started by `PyEvtxParser.stream_to_queue()`.
+/// Garbage collecting the handle stops the thread and waits for it to exit, dropping
+/// the error it failed with, if any (call `stop()` or `join()` to see it).
+pub struct PyQueueStream {
+    stopped: Arc<AtomicBool>,
+    handle: Option<JoinHandle<PyResult<()>>>,
+}
+
+#[pymethods]
+impl PyQueueStream {
+    /// stop(self, /)
+    /// --
+    ///
+    /// Stops the thread, and waits for it to exit.
+    /// Records it did not put on the queue yet are dropped, and so is the final `None`.
+    /// Raises the error the thread failed with, as `join()` does.
+    fn stop(&mut self, py: Python) -> PyResult<()> {
+        self.stopped.store(true, Ordering::Relaxed);
+        self.join(py)
+    }
+
+    /// join(self, /)
+    /// --
+    ///
+    /// Waits for the thread to exit, which it does once it put every record
+    /// and the final `None` on the queue (or once it is stopped).
+    /// Raises the error the thread failed with, if putting on the queue failed.
+    fn join(&mut self, py: Python) -> PyResult<()> {
+        match self.handle.take() {
+            // The thread needs the GIL to put records on the queue.
+            Some(handle) => match py.allow_threads(|| handle.join()) {
+                Ok(result) => result,
+                Err(_) => Err(PyErr::new::<PyRuntimeError, _>(
+                    "The thread of the stream panicked",
+                )),
+            },
+            None => Ok(()),
+        }
+    }
+
+    /// Whether the thread is still running.
+    #[getter]
+    fn running(&self) -> bool {
+        self.handle
+            .as_ref()
+            .is_some_and(|handle| !handle.is_finished())
+    }
+}
+
+/// Stops the thread and waits for it, which takes up to the timeout of its pending `put()`.
+impl Drop for PyQueueStream {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+
+        if let Some(handle) = self.handle.take() {
+            // The thread needs the GIL to put records on the queue.
+            let _ = Python::with_gil(|py| py.allow_threads(|| handle.join()));
+        }
+    }
+}
+
+#[pyclass]
+pub struct PyChunkChecksumsIterator {
+    inner: BoxedParser,
+    following: IntoIter<BoxedParser>,
+    /// The chunks of `inner` which are yet to be verified.
+    chunks: Range<u64>,
+}
+
+#[pymethods]
+impl PyChunkChecksumsIterator {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn __next__(&mut self) -> PyResult<Option<(u64, (u32, u32), (u32, u32), bool)>> {
+        loop {
+            let chunk = if self.chunks.is_empty() {
+                None
+            } else {
+                self.inner
+                    .find_next_chunk(self.chunks.start)
+                    .filter(|(_, chunk_id)| *chunk_id < self.chunks.end)
+            };
+
+            match chunk {
+                None => match self.following.next() {
+                    Some(parser) => {
+                        self.inner = parser;
+                        self.chunks = 0..u64::MAX;
+                    }
+                    None => return Ok(None),
+                },
+                Some((chunk, chunk_id)) => {
+                    self.chunks.start = chunk_id + 1;
+
+                    let checksums = ChunkChecksums::from_chunk(&chunk.map_err(PyEvtxError)?);
+                    return Ok(Some((
+                        chunk_id,
+                        checksums.stored,
+                        checksums.computed,
+                        checksums.is_valid(),
+                    )));
+                }
+            }
+        }
+    }
 }
 
 // Don't use double quotes ("") inside this docstring, this will crash pyo3.
@@ -384,6 +3989,15 @@ impl PyRecordsIterator {
 fn evtx(_py: Python, m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<PyEvtxParser>()?;
     m.add_class::<PyRecordsIterator>()?;
+    m.add_class::<PyEvtxRecord>()?;
+    m.add_class::<PyChunkChecksumsIterator>()?;
+    m.add_class::<PyQueueStream>()?;
+    m.add_class::<PyParserSettings>()?;
+    m.add_function(wrap_pyfunction!(build_info, m)?)?;
+    m.add_function(wrap_pyfunction!(has_wevt_support, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_chunk_bytes, m)?)?;
+    m.add_function(wrap_pyfunction!(benchmark, m)?)?;
+    m.add("__version__", env!("CARGO_PKG_VERSION"))?;
 
     Ok(())
 }
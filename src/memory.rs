@@ -0,0 +1,48 @@
+use crate::EVTX_CHUNK_SIZE;
+
+/// The string and template caches of a chunk, relative to the size of its data.
+/// Templates are kept as deserialized tokens, which take more room than their BinXml.
+const CACHE_FACTOR: u64 = 2;
+/// The tokens and serialized output of a record, relative to its size in the file.
+/// Records mostly consist of substitution values, which get expanded with their template.
+const EXPANSION_FACTOR: u64 = 4;
+
+/// A rough model of the memory held while parsing, in bytes.
+pub struct MemoryEstimate {
+    /// The input, when it is held in memory (given as `bytes`).
+    pub input: u64,
+    /// The largest record in the file, as stored.
+    pub largest_record: u64,
+    pub num_threads: u64,
+    /// Whether the binary values of records are copied out, see `extract_binary`.
+    pub extract_binary: bool,
+}
+
+impl MemoryEstimate {
+    /// A chunk's data along with its caches, which are held until its last record is yielded.
+    pub fn chunk(&self) -> u64 {
+        EVTX_CHUNK_SIZE * (1 + CACHE_FACTOR)
+    }
+
+    /// The largest record being deserialized and serialized.
+    pub fn record(&self) -> u64 {
+        let binary = if self.extract_binary {
+            self.largest_record
+        } else {
+            0
+        };
+
+        self.largest_record * EXPANSION_FACTOR + binary
+    }
+
+    /// Iterators deserialize a chunk at a time and a record at a time, whatever the thread count.
+    pub fn iterator_peak(&self) -> u64 {
+        self.input + self.chunk() + self.record()
+    }
+
+    /// `evtx`'s own iteration (as used by `benchmark()`) handles a chunk per thread at once,
+    /// and holds all the serialized records of those chunks.
+    pub fn parallel_peak(&self) -> u64 {
+        self.input + self.num_threads * (self.chunk() + EVTX_CHUNK_SIZE * EXPANSION_FACTOR)
+    }
+}
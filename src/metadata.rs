@@ -0,0 +1,175 @@
+use crate::walk::{is_named, name, value_text, walk, MissingTemplate, Node};
+
+use evtx_rs::err::EvtxError;
+use evtx_rs::EvtxRecord;
+use serde_json::Value;
+
+use std::ops::ControlFlow;
+
+/// Fields of the `<System>` element of a record, used for statistics and filtering.
+///
+/// These are read from the record's tokens, stopping at the end of `<System>`,
+/// without rendering the record.
+#[derive(Debug, Default)]
+pub struct RecordMetadata {
+    pub event_id: Option<u64>,
+    pub version: Option<u64>,
+    pub provider_name: Option<String>,
+    pub provider_guid: Option<String>,
+    pub channel: Option<String>,
+    pub keywords: Option<u64>,
+    /// The `ActivityID` of the `Correlation` element, linking the events of an activity.
+    pub activity_id: Option<String>,
+    /// The `RelatedActivityID` of the `Correlation` element, the activity this one stems from.
+    pub related_activity_id: Option<String>,
+}
+
+impl RecordMetadata {
+    #[allow(clippy::result_large_err)]
+    pub fn from_record(record: &EvtxRecord) -> Result<RecordMetadata, EvtxError> {
+        match RecordMetadata::from_tokens(record) {
+            Ok(metadata) => Ok(metadata),
+            Err(MissingTemplate) => {
+                let value = record.clone().into_json_value()?.data;
+                Ok(RecordMetadata::from_value(&value))
+            }
+        }
+    }
+
+    fn from_tokens(record: &EvtxRecord) -> Result<RecordMetadata, MissingTemplate> {
+        let chunk = record.chunk;
+        let mut metadata = RecordMetadata::default();
+        let mut in_system = false;
+        // The child of `<System>` being read, and its text so far.
+        let mut field = None;
+        let mut text: Option<String> = None;
+
+        walk(record, |node| {
+            match node {
+                Node::Element(element, 2) => in_system = is_named(chunk, element, "System"),
+                Node::Element(element, 3) if in_system => {
+                    field =
+                        name(chunk, element).and_then(|name| SystemField::from_name(name.as_str()));
+                    text = None;
+                }
+                Node::Attribute(attribute, value, 3) if field.is_some() => {
+                    let attribute = match name(chunk, attribute) {
+                        Some(attribute) => attribute,
+                        None => return ControlFlow::Continue(()),
+                    };
+                    let target = match (field, attribute.as_str()) {
+                        (Some(SystemField::Provider), "Name") => &mut metadata.provider_name,
+                        (Some(SystemField::Provider), "Guid") => &mut metadata.provider_guid,
+                        (Some(SystemField::Correlation), "ActivityID") => &mut metadata.activity_id,
+                        (Some(SystemField::Correlation), "RelatedActivityID") => {
+                            &mut metadata.related_activity_id
+                        }
+                        _ => return ControlFlow::Continue(()),
+                    };
+                    *target = value_text(value).map(String::from);
+                }
+                Node::Text(value, 3) if field.is_some() => {
+                    if let Some(value) = value_text(value) {
+                        text.get_or_insert_with(String::new).push_str(&value);
+                    }
+                }
+                Node::End(3) if in_system => {
+                    let text = text.take();
+                    match field.take() {
+                        Some(SystemField::EventId) => {
+                            metadata.event_id = text.and_then(|t| t.parse().ok())
+                        }
+                        Some(SystemField::Version) => {
+                            metadata.version = text.and_then(|t| t.parse().ok())
+                        }
+                        Some(SystemField::Channel) => metadata.channel = text,
+                        Some(SystemField::Keywords) => {
+                            metadata.keywords = text.and_then(|t| parse_keywords(&t))
+                        }
+                        _ => {}
+                    }
+                }
+                Node::End(2) if in_system => return ControlFlow::Break(()),
+                _ => {}
+            }
+
+            ControlFlow::Continue(())
+        })?;
+
+        Ok(metadata)
+    }
+
+    pub fn from_value(value: &Value) -> RecordMetadata {
+        let system = &value["Event"]["System"];
+
+        let provider = &system["Provider"];
+        let correlation = &system["Correlation"];
+
+        RecordMetadata {
+            event_id: as_u64(text(&system["EventID"])),
+            version: as_u64(text(&system["Version"])),
+            provider_name: as_string(attribute(provider, "Name")),
+            provider_guid: as_string(attribute(provider, "Guid")),
+            channel: as_string(text(&system["Channel"])),
+            keywords: as_keywords(&system["Keywords"]),
+            activity_id: as_string(attribute(correlation, "ActivityID")),
+            related_activity_id: as_string(attribute(correlation, "RelatedActivityID")),
+        }
+    }
+}
+
+/// Returns the text of an element, which is nested under `#text` when the element has attributes.
+pub fn text(element: &Value) -> &Value {
+    element.get("#text").unwrap_or(element)
+}
+
+/// Returns an attribute of an element, or `Value::Null` if it is missing.
+pub fn attribute<'a>(element: &'a Value, name: &str) -> &'a Value {
+    &element["#attributes"][name]
+}
+
+pub fn as_u64(value: &Value) -> Option<u64> {
+    match value {
+        Value::Number(n) => n.as_u64(),
+        Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Parses the `Keywords` bitmask, which is rendered as a hex string.
+pub fn as_keywords(value: &Value) -> Option<u64> {
+    value.as_str().and_then(parse_keywords)
+}
+
+fn parse_keywords(keywords: &str) -> Option<u64> {
+    u64::from_str_radix(keywords.trim_start_matches("0x"), 16).ok()
+}
+
+fn as_string(value: &Value) -> Option<String> {
+    value.as_str().map(str::to_string)
+}
+
+/// The children of `<System>` which `RecordMetadata` holds.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum SystemField {
+    EventId,
+    Version,
+    Provider,
+    Channel,
+    Keywords,
+    Correlation,
+}
+
+impl SystemField {
+    fn from_name(name: &str) -> Option<SystemField> {
+        match name {
+            "EventID" => Some(SystemField::EventId),
+            "Version" => Some(SystemField::Version),
+            "Provider" => Some(SystemField::Provider),
+            "Channel" => Some(SystemField::Channel),
+            "Keywords" => Some(SystemField::Keywords),
+            "Correlation" => Some(SystemField::Correlation),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,46 @@
+use evtx_rs::err::SerializationError;
+use rmp::encode;
+use serde_json::Value;
+
+/// A value of a record map, as encoded by `encode_map`.
+pub enum Field<'a> {
+    UInt(u64),
+    Str(&'a str),
+    /// The structured form of a record, encoded as nested maps and arrays.
+    Value(&'a Value),
+    /// Encoded as an array of `bin` values, which Python decodes to `bytes`.
+    Binaries(&'a [Vec<u8>]),
+}
+
+/// Encodes `fields` as a MessagePack map, in order.
+pub fn encode_map(fields: &[(&str, Field)]) -> Result<Vec<u8>, SerializationError> {
+    let mut buf = Vec::new();
+
+    // Writing to a `Vec` cannot fail, only serializing a value can.
+    encode::write_map_len(&mut buf, fields.len() as u32).expect("writing to a Vec");
+    for (key, field) in fields {
+        encode::write_str(&mut buf, key).expect("writing to a Vec");
+
+        match field {
+            Field::UInt(n) => {
+                encode::write_uint(&mut buf, *n).expect("writing to a Vec");
+            }
+            Field::Str(s) => encode::write_str(&mut buf, s).expect("writing to a Vec"),
+            Field::Value(value) => {
+                rmp_serde::encode::write_named(&mut buf, value).map_err(|e| {
+                    SerializationError::JsonStructureError {
+                        message: format!("Encoding MessagePack failed: {}", e),
+                    }
+                })?
+            }
+            Field::Binaries(binaries) => {
+                encode::write_array_len(&mut buf, binaries.len() as u32).expect("writing to a Vec");
+                for binary in binaries.iter() {
+                    encode::write_bin(&mut buf, binary).expect("writing to a Vec");
+                }
+            }
+        }
+    }
+
+    Ok(buf)
+}
@@ -0,0 +1,122 @@
+use evtx_rs::err::DeserializationError;
+use evtx_rs::{EvtxChunkData, EvtxChunkHeader, EvtxRecordHeader};
+
+use std::io::Cursor;
+
+pub const EVTX_CHUNK_HEADER_SIZE: usize = 512;
+/// Size of the record header, plus the trailing copy of the record size.
+const EVTX_RECORD_OVERHEAD: u32 = 24 + 4;
+/// `ChunkFlags::DIRTY`, which `evtx` does not export.
+const CHUNK_FLAG_DIRTY: u32 = 0x1;
+
+/// A record located inside a chunk, without its BinXml being deserialized.
+pub struct RawRecord<'a> {
+    pub header: EvtxRecordHeader,
+    /// The whole record, including its header.
+    pub data: &'a [u8],
+}
+
+/// The header of a record, and where the record is in its chunk.
+pub struct LocatedHeader {
+    pub header: EvtxRecordHeader,
+    /// The offset of the record in the chunk.
+    pub offset: u64,
+    /// The offset of the record's BinXml in the chunk, following its header.
+    pub binxml_offset: u64,
+}
+
+/// Walks the record headers of a chunk, in the same manner as `EvtxChunk::iter`.
+pub struct RecordHeaders {
+    offset: u64,
+    exhausted: bool,
+    /// Whether the chunk is flagged as dirty, meaning it was still being written to.
+    dirty: bool,
+    truncated: bool,
+}
+
+impl RecordHeaders {
+    pub fn new(chunk_header: &EvtxChunkHeader) -> Self {
+        RecordHeaders {
+            offset: EVTX_CHUNK_HEADER_SIZE as u64,
+            exhausted: false,
+            dirty: chunk_header.flags.bits() & CHUNK_FLAG_DIRTY != 0,
+            truncated: false,
+        }
+    }
+
+    /// Whether the walk stopped at an incomplete record at the end of a dirty chunk
+    /// (such as the active chunk of a file copied from a running system).
+    pub fn truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Reads the header of the next record of the chunk made of `chunk_header` and `data`.
+    ///
+    /// Like `evtx`, the walk doesn't try to recover after an invalid record header:
+    /// its error is returned, and the walk ends.
+    pub fn next(
+        &mut self,
+        chunk_header: &EvtxChunkHeader,
+        data: &[u8],
+    ) -> Option<Result<LocatedHeader, DeserializationError>> {
+        let free_space_offset = u64::from(chunk_header.free_space_offset);
+        if self.exhausted || self.offset >= free_space_offset {
+            return None;
+        }
+
+        let mut cursor = Cursor::new(data.get(self.offset as usize..)?);
+        let header = match EvtxRecordHeader::from_reader(&mut cursor) {
+            Ok(header)
+                if self.dirty && self.offset + u64::from(header.data_size) > free_space_offset =>
+            {
+                self.exhausted = true;
+                self.truncated = true;
+                return None;
+            }
+            Ok(header) if header.data_size >= EVTX_RECORD_OVERHEAD => header,
+            Ok(_) => {
+                self.exhausted = true;
+                return None;
+            }
+            Err(_) if self.dirty => {
+                // A record which was being written when the file was copied.
+                self.exhausted = true;
+                self.truncated = true;
+                return None;
+            }
+            Err(e) => {
+                self.exhausted = true;
+                return Some(Err(e));
+            }
+        };
+
+        let offset = self.offset;
+        self.offset += u64::from(header.data_size);
+        if chunk_header.last_event_record_id == header.event_record_id {
+            self.exhausted = true;
+        }
+
+        Some(Ok(LocatedHeader {
+            header,
+            offset,
+            binxml_offset: offset + cursor.position(),
+        }))
+    }
+}
+
+/// Walks the records of a chunk, without deserializing them.
+/// Stops at the first record with an invalid header, or which doesn't fit in the chunk.
+pub fn iter_raw_records(chunk: &EvtxChunkData) -> impl Iterator<Item = RawRecord<'_>> + '_ {
+    let mut headers = RecordHeaders::new(&chunk.header);
+
+    std::iter::from_fn(move || {
+        let LocatedHeader { header, offset, .. } =
+            headers.next(&chunk.header, &chunk.data)?.ok()?;
+        let data = chunk
+            .data
+            .get(offset as usize..(offset + u64::from(header.data_size)) as usize)?;
+
+        Some(RawRecord { header, data })
+    })
+    .fuse()
+}
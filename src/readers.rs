@@ -0,0 +1,353 @@
+use crate::{
+    ReadSeek, EVTX_CHUNK_SIZE, EVTX_FILE_HEADER_SIZE, EVTX_FILE_MAGIC, HEADER_BLOCK_SIZE_OFFSET,
+};
+
+use pyo3::types::PyBytes;
+use pyo3::{Py, Python};
+
+use std::io::{self, Read, Seek, SeekFrom};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+pub type SharedReader = Arc<Mutex<Box<dyn ReadSeek>>>;
+
+fn lock(reader: &SharedReader) -> io::Result<MutexGuard<'_, Box<dyn ReadSeek>>> {
+    reader
+        .lock()
+        .map_err(|_| io::Error::other("reader lock was poisoned"))
+}
+
+/// A view of `[start, end)` of a reader which may be shared with other windows.
+/// Offsets are relative to `start`, so the window looks like a stream of its own.
+pub struct Window {
+    inner: SharedReader,
+    start: u64,
+    end: u64,
+    position: u64,
+}
+
+impl Window {
+    pub fn new(inner: SharedReader, start: u64, end: u64) -> Window {
+        Window {
+            inner,
+            start,
+            end,
+            position: 0,
+        }
+    }
+
+    fn len(&self) -> u64 {
+        self.end - self.start
+    }
+}
+
+impl Read for Window {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.len().saturating_sub(self.position);
+        let max = buf.len().min(remaining as usize);
+        if max == 0 {
+            return Ok(0);
+        }
+
+        let mut inner = lock(&self.inner)?;
+        inner.seek(SeekFrom::Start(self.start + self.position))?;
+        let read = inner.read(&mut buf[..max])?;
+        self.position += read as u64;
+
+        Ok(read)
+    }
+}
+
+impl Seek for Window {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.position = seek_position(pos, self.position, self.len())?;
+        Ok(self.position)
+    }
+}
+
+/// A view of an EVTX file whose first chunk is not at the standard offset,
+/// which looks like a standard file to `evtx`: the header block is cut or padded
+/// to `EVTX_FILE_HEADER_SIZE` bytes, and the chunks follow right after it.
+pub struct Rebased {
+    inner: Box<dyn ReadSeek>,
+    header: Vec<u8>,
+    first_chunk_offset: u64,
+    len: u64,
+    position: u64,
+}
+
+impl Rebased {
+    pub fn new(mut inner: Box<dyn ReadSeek>, first_chunk_offset: u64) -> io::Result<Rebased> {
+        let len = inner.seek(SeekFrom::End(0))?;
+        inner.seek(SeekFrom::Start(0))?;
+
+        let mut header = Vec::with_capacity(EVTX_FILE_HEADER_SIZE as usize);
+        inner
+            .by_ref()
+            .take(first_chunk_offset.min(EVTX_FILE_HEADER_SIZE))
+            .read_to_end(&mut header)?;
+        header.resize(EVTX_FILE_HEADER_SIZE as usize, 0);
+        // `evtx` counts the chunks from the header block size.
+        header[HEADER_BLOCK_SIZE_OFFSET..HEADER_BLOCK_SIZE_OFFSET + 2]
+            .copy_from_slice(&(EVTX_FILE_HEADER_SIZE as u16).to_le_bytes());
+
+        Ok(Rebased {
+            inner,
+            header,
+            first_chunk_offset,
+            len: EVTX_FILE_HEADER_SIZE + len.saturating_sub(first_chunk_offset),
+            position: 0,
+        })
+    }
+}
+
+impl Read for Rebased {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = if self.position < EVTX_FILE_HEADER_SIZE {
+            let header = &self.header[self.position as usize..];
+            let read = buf.len().min(header.len());
+            buf[..read].copy_from_slice(&header[..read]);
+            read
+        } else {
+            let remaining = self.len.saturating_sub(self.position);
+            let max = buf.len().min(remaining as usize);
+            if max == 0 {
+                return Ok(0);
+            }
+
+            let offset = self.first_chunk_offset + (self.position - EVTX_FILE_HEADER_SIZE);
+            self.inner.seek(SeekFrom::Start(offset))?;
+            self.inner.read(&mut buf[..max])?
+        };
+        self.position += read as u64;
+
+        Ok(read)
+    }
+}
+
+impl Seek for Rebased {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.position = seek_position(pos, self.position, self.len)?;
+        Ok(self.position)
+    }
+}
+
+/// The delay before the first retry of `Retrying`, doubled for every following one.
+const RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// The last error a `Retrying` stream gave up on, kept aside because `evtx` swallows
+/// some of them (a chunk which fails to be read is reported as an incomplete chunk).
+#[derive(Clone, Default)]
+pub struct ReadFailure(Arc<Mutex<Option<String>>>);
+
+impl ReadFailure {
+    fn set(&self, message: String) {
+        if let Ok(mut failure) = self.0.lock() {
+            *failure = Some(message);
+        }
+    }
+
+    pub fn take(&self) -> Option<String> {
+        self.0.lock().ok()?.take()
+    }
+}
+
+/// A stream whose failed reads and seeks are retried up to `retries` times, with an exponential
+/// backoff, for file-like objects backed by a network connection which may drop now and then.
+///
+/// Errors name the stream, since they otherwise surface in the middle of a parsing error.
+pub struct Retrying<T> {
+    inner: T,
+    /// A description of the stream, such as the `repr()` of a file-like object.
+    source: String,
+    retries: u32,
+    position: u64,
+    failure: ReadFailure,
+}
+
+impl<T: Read + Seek> Retrying<T> {
+    pub fn new(inner: T, source: String, retries: u32, failure: ReadFailure) -> Retrying<T> {
+        Retrying {
+            inner,
+            source,
+            retries,
+            position: 0,
+            failure,
+        }
+    }
+
+    /// Runs `op` (given the number of the attempt) until it succeeds or retries are exhausted.
+    fn retry<R>(
+        &mut self,
+        action: &str,
+        mut op: impl FnMut(&mut T, u64, u32) -> io::Result<R>,
+    ) -> io::Result<R> {
+        let mut attempt = 0;
+
+        loop {
+            match op(&mut self.inner, self.position, attempt) {
+                Ok(result) => return Ok(result),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(_) if attempt < self.retries => {
+                    let delay = RETRY_DELAY * 2_u32.saturating_pow(attempt);
+                    // Let other threads run while waiting.
+                    Python::with_gil(|py| py.allow_threads(|| std::thread::sleep(delay)));
+                    attempt += 1;
+                }
+                Err(e) => {
+                    let message = format!(
+                        "failed to {} {} (after {} attempts): {}",
+                        action,
+                        self.source,
+                        attempt + 1,
+                        e
+                    );
+                    self.failure.set(message.clone());
+
+                    return Err(io::Error::new(e.kind(), message));
+                }
+            }
+        }
+    }
+}
+
+impl<T: Read + Seek> Read for Retrying<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.retry("read from", |inner, position, attempt| {
+            // A failed read may have moved the stream.
+            if attempt > 0 {
+                inner.seek(SeekFrom::Start(position))?;
+            }
+            inner.read(buf)
+        })?;
+        self.position += read as u64;
+
+        Ok(read)
+    }
+}
+
+impl<T: Read + Seek> Seek for Retrying<T> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        // The stream may have moved after a failed read, so relative seeks are made absolute.
+        let pos = match pos {
+            SeekFrom::Current(_) => SeekFrom::Start(seek_position(pos, self.position, 0)?),
+            pos => pos,
+        };
+
+        self.position = self.retry("seek in", |inner, _, _| inner.seek(pos))?;
+        Ok(self.position)
+    }
+}
+
+/// Resolves `pos` against the current position and the length of a stream.
+fn seek_position(pos: SeekFrom, position: u64, len: u64) -> io::Result<u64> {
+    let position = match pos {
+        SeekFrom::Start(offset) => Some(offset),
+        SeekFrom::End(offset) => len.checked_add_signed(offset),
+        SeekFrom::Current(offset) => position.checked_add_signed(offset),
+    };
+
+    position.ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "invalid seek to a negative or overflowing position",
+        )
+    })
+}
+
+/// A stream over several `bytes` objects, read as if they were concatenated,
+/// without copying them into a single buffer.
+#[derive(Debug)]
+pub struct ConcatenatedBuffers {
+    buffers: Vec<Py<PyBytes>>,
+    /// The offset of each buffer in the stream.
+    starts: Vec<u64>,
+    len: u64,
+    position: u64,
+}
+
+impl ConcatenatedBuffers {
+    pub fn new(py: Python, buffers: Vec<Py<PyBytes>>) -> ConcatenatedBuffers {
+        let mut starts = Vec::with_capacity(buffers.len());
+        let mut len = 0;
+
+        for buffer in &buffers {
+            starts.push(len);
+            len += buffer.as_bytes(py).len() as u64;
+        }
+
+        ConcatenatedBuffers {
+            buffers,
+            starts,
+            len,
+            position: 0,
+        }
+    }
+}
+
+impl Read for ConcatenatedBuffers {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.position >= self.len || buf.is_empty() {
+            return Ok(0);
+        }
+
+        // The last buffer starting at or before the position, which skips over empty buffers.
+        let index = self.starts.partition_point(|&start| start <= self.position) - 1;
+        let offset = (self.position - self.starts[index]) as usize;
+
+        let read = Python::with_gil(|py| {
+            let data = &self.buffers[index].as_bytes(py)[offset..];
+            let read = buf.len().min(data.len());
+            buf[..read].copy_from_slice(&data[..read]);
+            read
+        });
+        self.position += read as u64;
+
+        Ok(read)
+    }
+}
+
+impl Seek for ConcatenatedBuffers {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.position = seek_position(pos, self.position, self.len)?;
+        Ok(self.position)
+    }
+}
+
+/// Splits a stream holding several EVTX files back to back into a window per file.
+///
+/// A new file is detected when a file header is found where the next chunk is expected.
+pub fn split_concatenated_files(reader: Box<dyn ReadSeek>) -> io::Result<Vec<Window>> {
+    let reader: SharedReader = Arc::new(Mutex::new(reader));
+    let mut starts = vec![0];
+
+    let len = {
+        let mut inner = lock(&reader)?;
+        let len = inner.seek(SeekFrom::End(0))?;
+
+        let mut offset = EVTX_FILE_HEADER_SIZE;
+        let mut magic = [0_u8; 8];
+
+        while offset + magic.len() as u64 <= len {
+            inner.seek(SeekFrom::Start(offset))?;
+            inner.read_exact(&mut magic)?;
+
+            if &magic == EVTX_FILE_MAGIC {
+                starts.push(offset);
+                offset += EVTX_FILE_HEADER_SIZE;
+            } else {
+                offset += EVTX_CHUNK_SIZE;
+            }
+        }
+
+        len
+    };
+
+    let ends = starts.iter().skip(1).copied().chain(Some(len));
+
+    Ok(starts
+        .iter()
+        .zip(ends)
+        .map(|(&start, end)| Window::new(Arc::clone(&reader), start, end))
+        .collect())
+}
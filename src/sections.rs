@@ -0,0 +1,94 @@
+use quick_xml::events::Event;
+use quick_xml::{Reader, Writer};
+use serde_json::Value;
+
+/// The sections (children of the `Event` element) to keep in records.
+pub struct SectionFilter {
+    include: Option<Vec<String>>,
+    exclude: Vec<String>,
+}
+
+impl SectionFilter {
+    /// Returns `None` when neither list is given, as every section is kept then.
+    pub fn new(include: Option<Vec<String>>, exclude: Option<Vec<String>>) -> Option<Self> {
+        if include.is_none() && exclude.is_none() {
+            return None;
+        }
+
+        Some(SectionFilter {
+            include,
+            exclude: exclude.unwrap_or_default(),
+        })
+    }
+
+    fn accepts(&self, section: &str) -> bool {
+        self.include
+            .as_ref()
+            .is_none_or(|include| include.iter().any(|s| s == section))
+            && !self.exclude.iter().any(|s| s == section)
+    }
+
+    /// Removes the sections of `value` which are not accepted, in place.
+    pub fn apply(&self, value: &mut Value) {
+        if let Some(Value::Object(event)) = value.get_mut("Event") {
+            event.retain(|section, _| self.accepts(section));
+        }
+    }
+    /// Removes the sections of the XML record `xml` which are not accepted,
+    /// along with the whitespace indenting them.
+    pub fn apply_xml(&self, xml: String) -> Result<String, quick_xml::Error> {
+        if self.include.is_none() && !self.exclude.iter().any(|s| xml.contains(s.as_str())) {
+            return Ok(xml);
+        }
+
+        let mut reader = Reader::from_str(&xml);
+        let mut writer = Writer::new(Vec::with_capacity(xml.len()));
+        let mut depth = 0;
+        // Whitespace is held back until it's known whether the element it indents is kept.
+        let mut indent = None;
+
+        loop {
+            let event = reader.read_event()?;
+            let section = match &event {
+                Event::Start(e) | Event::Empty(e) if depth == 1 => {
+                    Some(String::from_utf8_lossy(e.local_name().as_ref()).into_owned())
+                }
+                _ => None,
+            };
+
+            match event {
+                Event::Eof => break,
+                Event::Text(ref text) if text.iter().all(u8::is_ascii_whitespace) => {
+                    if let Some(indent) = indent.replace(event) {
+                        writer.write_event(indent)?;
+                    }
+                    continue;
+                }
+                Event::Start(ref e) if section.as_deref().is_some_and(|s| !self.accepts(s)) => {
+                    reader.read_to_end(e.name())?;
+                    indent = None;
+                    continue;
+                }
+                Event::Empty(_) if section.as_deref().is_some_and(|s| !self.accepts(s)) => {
+                    indent = None;
+                    continue;
+                }
+                Event::Start(_) => depth += 1,
+                Event::End(_) => depth -= 1,
+                _ => {}
+            }
+
+            if let Some(indent) = indent.take() {
+                writer.write_event(indent)?;
+            }
+            writer.write_event(event)?;
+        }
+
+        if let Some(indent) = indent {
+            writer.write_event(indent)?;
+        }
+
+        // The events are written back as they were read, so the output is as valid as `xml`.
+        Ok(String::from_utf8(writer.into_inner()).expect("the input is a string"))
+    }
+}
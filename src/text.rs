@@ -0,0 +1,104 @@
+use crate::walk::{name, walk, MissingTemplate, Node};
+
+use evtx_rs::binxml::value_variant::BinXmlValue;
+use evtx_rs::err::EvtxError;
+use evtx_rs::EvtxRecord;
+use serde_json::Value;
+
+use std::ops::ControlFlow;
+
+/// Returns the text content of the data values of a record (everything but `System`),
+/// in document order, one value per line. Element names and attributes are left out,
+/// and nothing is escaped.
+pub fn text_content(record: &Value) -> String {
+    let mut lines = vec![];
+
+    if let Some(Value::Object(event)) = record.get("Event") {
+        for (section, value) in event {
+            if section != "System" && section != "#attributes" {
+                collect_text(value, &mut lines);
+            }
+        }
+    }
+
+    lines.join("\n")
+}
+
+/// Whether the record carries a payload: whether its `EventData` or `UserData` section
+/// holds any value other than nulls and blank strings (element names and attributes don't count).
+pub fn has_payload(record: &Value) -> bool {
+    let mut lines = vec![];
+
+    for section in ["EventData", "UserData"] {
+        if let Some(value) = record["Event"].get(section) {
+            collect_text(value, &mut lines);
+        }
+    }
+
+    lines.iter().any(|line| !line.trim().is_empty())
+}
+
+/// Whether the record carries a payload, as in `has_payload`, read from the record's tokens
+/// (stopping at the first value found) rather than from its rendered value.
+#[allow(clippy::result_large_err)]
+pub fn record_has_payload(record: &EvtxRecord) -> Result<bool, EvtxError> {
+    let chunk = record.chunk;
+    let mut in_payload = false;
+    let mut found = false;
+
+    let walked = walk(record, |node| {
+        match node {
+            Node::Element(element, 2) => {
+                in_payload = name(chunk, element)
+                    .is_some_and(|name| matches!(name.as_str(), "EventData" | "UserData"));
+            }
+            Node::End(2) => in_payload = false,
+            Node::Text(value, _) if in_payload && !is_blank(value) => {
+                found = true;
+                return ControlFlow::Break(());
+            }
+            _ => {}
+        }
+
+        ControlFlow::Continue(())
+    });
+
+    match walked {
+        Ok(()) => Ok(found),
+        Err(MissingTemplate) => Ok(has_payload(&record.clone().into_json_value()?.data)),
+    }
+}
+
+/// Whether a value renders as nothing but a null or whitespace.
+fn is_blank(value: &BinXmlValue) -> bool {
+    match value {
+        BinXmlValue::NullType => true,
+        BinXmlValue::StringType(s) => s.trim().is_empty(),
+        BinXmlValue::AnsiStringType(s)
+        | BinXmlValue::HexInt32Type(s)
+        | BinXmlValue::HexInt64Type(s) => s.trim().is_empty(),
+        BinXmlValue::StringArrayType(strings) => strings.iter().all(|s| s.trim().is_empty()),
+        BinXmlValue::BinaryType(bytes) => bytes.is_empty(),
+        _ => false,
+    }
+}
+
+fn collect_text(value: &Value, lines: &mut Vec<String>) {
+    match value {
+        Value::Null => {}
+        Value::String(s) => lines.push(s.clone()),
+        Value::Bool(_) | Value::Number(_) => lines.push(value.to_string()),
+        Value::Array(values) => {
+            for value in values {
+                collect_text(value, lines);
+            }
+        }
+        Value::Object(map) => {
+            for (key, value) in map {
+                if key != "#attributes" {
+                    collect_text(value, lines);
+                }
+            }
+        }
+    }
+}
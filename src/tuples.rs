@@ -0,0 +1,95 @@
+use crate::values::to_pyobject;
+use crate::{format_timestamp, ParsedRecord};
+
+use pyo3::exceptions::PyValueError;
+use pyo3::types::PyTuple;
+use pyo3::{Bound, PyErr, PyResult, Python};
+
+/// A field of the tuples yielded by `records_tuples()`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TupleField {
+    EventRecordId,
+    Timestamp,
+    Data,
+    EventId,
+    Version,
+    Provider,
+    ProviderGuid,
+    Channel,
+}
+
+impl TupleField {
+    pub fn from_name(name: &str) -> Result<TupleField, PyErr> {
+        match name {
+            "event_record_id" => Ok(TupleField::EventRecordId),
+            "timestamp" => Ok(TupleField::Timestamp),
+            "data" => Ok(TupleField::Data),
+            "event_id" => Ok(TupleField::EventId),
+            "version" => Ok(TupleField::Version),
+            "provider" => Ok(TupleField::Provider),
+            "provider_guid" => Ok(TupleField::ProviderGuid),
+            "channel" => Ok(TupleField::Channel),
+            _ => Err(PyErr::new::<PyValueError, _>(format!(
+                "Unknown field `[{}]`, possible values are: event_record_id, timestamp, data, \
+                 event_id, version, provider, provider_guid, channel",
+                name
+            ))),
+        }
+    }
+
+    /// Whether the field is read from the `<System>` element, rather than the record header.
+    pub fn is_metadata(self) -> bool {
+        !matches!(
+            self,
+            TupleField::EventRecordId | TupleField::Timestamp | TupleField::Data
+        )
+    }
+}
+
+/// Parses the names of the fields of a tuple, which must not repeat.
+pub fn tuple_fields(names: &[String]) -> Result<Vec<TupleField>, PyErr> {
+    let mut fields = Vec::with_capacity(names.len());
+
+    for name in names {
+        let field = TupleField::from_name(name)?;
+        if fields.contains(&field) {
+            return Err(PyErr::new::<PyValueError, _>(format!(
+                "Field `{}` is listed more than once",
+                name
+            )));
+        }
+        fields.push(field);
+    }
+
+    Ok(fields)
+}
+
+/// Converts a record to a tuple of `fields`, in order, with `None` for missing metadata.
+pub fn record_to_pytuple<'py>(
+    py: Python<'py>,
+    parsed: ParsedRecord,
+    fields: &[TupleField],
+    timestamp_suffix: bool,
+) -> PyResult<Bound<'py, PyTuple>> {
+    let record = parsed.record;
+    let (event_record_id, timestamp) = (record.event_record_id, record.timestamp);
+    let metadata = parsed.metadata.unwrap_or_default();
+
+    let mut data = Some(record.data);
+    let values = fields
+        .iter()
+        .map(|field| match field {
+            TupleField::EventRecordId => to_pyobject(py, event_record_id),
+            TupleField::Timestamp => to_pyobject(py, format_timestamp(timestamp, timestamp_suffix)),
+            // Fields are unique, so the data is only ever moved out once.
+            TupleField::Data => to_pyobject(py, data.take().unwrap_or_default()),
+            TupleField::EventId => to_pyobject(py, metadata.event_id),
+            TupleField::Version => to_pyobject(py, metadata.version),
+            TupleField::Provider => to_pyobject(py, &metadata.provider_name),
+            TupleField::ProviderGuid => to_pyobject(py, &metadata.provider_guid),
+            TupleField::Channel => to_pyobject(py, &metadata.channel),
+        })
+        .collect::<PyResult<Vec<_>>>()?;
+
+    PyTuple::new(py, values)
+}
@@ -0,0 +1,188 @@
+use evtx_rs::binxml::value_variant::BinXmlValue;
+use evtx_rs::model::deserialized::{BinXMLDeserializedTokens, BinXmlTemplateRef};
+use evtx_rs::EvtxRecord;
+use pyo3::types::{PyBytes, PyDict, PyDictMethods, PyList, PyListMethods};
+use pyo3::{BoundObject, IntoPyObject, PyErr, PyObject, PyResult, Python};
+use serde_json::Value;
+
+/// Calls `f` with every value in `tokens`, including the substitution values
+/// of template instances and the contents of nested BinXml values.
+pub fn visit_values<'a>(
+    tokens: &'a [BinXMLDeserializedTokens<'a>],
+    f: &mut impl FnMut(&'a BinXmlValue<'a>),
+) {
+    for token in tokens {
+        match token {
+            BinXMLDeserializedTokens::Value(value) => visit_value(value, f),
+            BinXMLDeserializedTokens::TemplateInstance(template) => {
+                visit_values(&template.substitution_array, f)
+            }
+            _ => {}
+        }
+    }
+}
+
+fn visit_value<'a>(value: &'a BinXmlValue<'a>, f: &mut impl FnMut(&'a BinXmlValue<'a>)) {
+    match value {
+        BinXmlValue::BinXmlType(tokens) => visit_values(tokens, f),
+        _ => f(value),
+    }
+}
+
+/// Returns the contents of every binary value in the record, in document order.
+pub fn binary_values(record: &EvtxRecord) -> Vec<Vec<u8>> {
+    let mut values = vec![];
+
+    visit_values(&record.tokens, &mut |value| {
+        if let BinXmlValue::BinaryType(bytes) = value {
+            values.push(bytes.to_vec());
+        }
+    });
+
+    values
+}
+
+/// Returns the template instances of the record, in document order
+/// (an instance nested in a BinXml value follows the instance holding it).
+pub fn template_instances<'a>(
+    tokens: &'a [BinXMLDeserializedTokens<'a>],
+) -> Vec<&'a BinXmlTemplateRef<'a>> {
+    let mut instances = vec![];
+    collect_template_instances(tokens, &mut instances);
+    instances
+}
+
+/// Returns the GUID of the template definition at `offset` in the chunk,
+/// formatted like the GUIDs of records, or `None` if the chunk is too short to hold it.
+pub fn template_guid(chunk_data: &[u8], offset: u32) -> Option<String> {
+    // A definition starts with the offset of the next template in its bucket, then the GUID.
+    let start = offset as usize + 4;
+    let guid = chunk_data.get(start..start + 16)?;
+
+    Some(format!(
+        "{:08X}-{:04X}-{:04X}-{:02X}{:02X}-{}",
+        u32::from_le_bytes([guid[0], guid[1], guid[2], guid[3]]),
+        u16::from_le_bytes([guid[4], guid[5]]),
+        u16::from_le_bytes([guid[6], guid[7]]),
+        guid[8],
+        guid[9],
+        guid[10..]
+            .iter()
+            .map(|b| format!("{:02X}", b))
+            .collect::<String>()
+    ))
+}
+
+fn collect_template_instances<'a>(
+    tokens: &'a [BinXMLDeserializedTokens<'a>],
+    instances: &mut Vec<&'a BinXmlTemplateRef<'a>>,
+) {
+    for token in tokens {
+        match token {
+            BinXMLDeserializedTokens::TemplateInstance(template) => {
+                instances.push(template);
+                collect_template_instances(&template.substitution_array, instances);
+            }
+            BinXMLDeserializedTokens::Value(BinXmlValue::BinXmlType(tokens)) => {
+                collect_template_instances(tokens, instances)
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Converts a substitution value to the closest Python type.
+///
+/// Binary values become `bytes` and timestamps `datetime` objects, while GUIDs, SIDs and
+/// hex integers keep their textual form. Nested BinXml (and the handle types, which never
+/// appear in records) become `None`.
+pub fn value_to_pyobject(py: Python<'_>, value: &BinXmlValue) -> PyResult<PyObject> {
+    match value {
+        BinXmlValue::StringType(s) => to_pyobject(py, s),
+        BinXmlValue::AnsiStringType(s) => to_pyobject(py, s.as_ref()),
+        BinXmlValue::Int8Type(n) => to_pyobject(py, n),
+        BinXmlValue::UInt8Type(n) => to_pyobject(py, n),
+        BinXmlValue::Int16Type(n) => to_pyobject(py, n),
+        BinXmlValue::UInt16Type(n) => to_pyobject(py, n),
+        BinXmlValue::Int32Type(n) => to_pyobject(py, n),
+        BinXmlValue::UInt32Type(n) => to_pyobject(py, n),
+        BinXmlValue::Int64Type(n) => to_pyobject(py, n),
+        BinXmlValue::UInt64Type(n) => to_pyobject(py, n),
+        BinXmlValue::Real32Type(n) => to_pyobject(py, n),
+        BinXmlValue::Real64Type(n) => to_pyobject(py, n),
+        BinXmlValue::BoolType(b) => to_pyobject(py, b),
+        BinXmlValue::BinaryType(bytes) => to_pyobject(py, PyBytes::new(py, bytes)),
+        BinXmlValue::GuidType(guid) => to_pyobject(py, guid.to_string()),
+        BinXmlValue::SizeTType(n) => to_pyobject(py, n),
+        BinXmlValue::FileTimeType(t) | BinXmlValue::SysTimeType(t) => to_pyobject(py, t),
+        BinXmlValue::SidType(sid) => to_pyobject(py, sid.to_string()),
+        BinXmlValue::HexInt32Type(s) | BinXmlValue::HexInt64Type(s) => to_pyobject(py, s.as_ref()),
+        BinXmlValue::StringArrayType(v) => to_pyobject(py, v),
+        BinXmlValue::Int8ArrayType(v) => to_pyobject(py, v),
+        // `Vec<u8>` would become `bytes`.
+        BinXmlValue::UInt8ArrayType(v) => {
+            to_pyobject(py, v.iter().map(|n| u16::from(*n)).collect::<Vec<_>>())
+        }
+        BinXmlValue::Int16ArrayType(v) => to_pyobject(py, v),
+        BinXmlValue::UInt16ArrayType(v) => to_pyobject(py, v),
+        BinXmlValue::Int32ArrayType(v) => to_pyobject(py, v),
+        BinXmlValue::UInt32ArrayType(v) => to_pyobject(py, v),
+        BinXmlValue::Int64ArrayType(v) => to_pyobject(py, v),
+        BinXmlValue::UInt64ArrayType(v) => to_pyobject(py, v),
+        BinXmlValue::Real32ArrayType(v) => to_pyobject(py, v),
+        BinXmlValue::Real64ArrayType(v) => to_pyobject(py, v),
+        BinXmlValue::BoolArrayType(v) => to_pyobject(py, v),
+        BinXmlValue::GuidArrayType(v) => to_pyobject(py, to_strings(v)),
+        BinXmlValue::FileTimeArrayType(v) | BinXmlValue::SysTimeArrayType(v) => to_pyobject(py, v),
+        BinXmlValue::SidArrayType(v) => to_pyobject(py, to_strings(v)),
+        BinXmlValue::HexInt32ArrayType(v) | BinXmlValue::HexInt64ArrayType(v) => {
+            to_pyobject(py, to_strings(v))
+        }
+        _ => Ok(py.None()),
+    }
+}
+
+/// Converts a JSON value to the matching Python object.
+pub fn json_to_pyobject(py: Python<'_>, value: &Value) -> PyResult<PyObject> {
+    match value {
+        Value::Null => Ok(py.None()),
+        Value::Bool(b) => to_pyobject(py, b),
+        Value::Number(n) => match (n.as_u64(), n.as_i64()) {
+            (Some(n), _) => to_pyobject(py, n),
+            (None, Some(n)) => to_pyobject(py, n),
+            _ => to_pyobject(py, n.as_f64()),
+        },
+        Value::String(s) => to_pyobject(py, s),
+        Value::Array(values) => {
+            let list = PyList::empty(py);
+            for value in values {
+                list.append(json_to_pyobject(py, value)?)?;
+            }
+            to_pyobject(py, list)
+        }
+        Value::Object(map) => {
+            let dict = PyDict::new(py);
+            for (key, value) in map {
+                dict.set_item(key, json_to_pyobject(py, value)?)?;
+            }
+            to_pyobject(py, dict)
+        }
+    }
+}
+
+pub fn to_pyobject<'py, T>(py: Python<'py>, value: T) -> PyResult<PyObject>
+where
+    T: IntoPyObject<'py>,
+    T::Error: Into<PyErr>,
+{
+    Ok(value
+        .into_pyobject(py)
+        .map_err(Into::into)?
+        .into_bound()
+        .into_any()
+        .unbind())
+}
+
+fn to_strings<T: ToString>(values: &[T]) -> Vec<String> {
+    values.iter().map(ToString::to_string).collect()
+}
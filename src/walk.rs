@@ -0,0 +1,175 @@
+use evtx_rs::binxml::name::{BinXmlName, BinXmlNameRef};
+use evtx_rs::binxml::value_variant::BinXmlValue;
+use evtx_rs::model::deserialized::BinXMLDeserializedTokens;
+use evtx_rs::{EvtxChunk, EvtxRecord};
+
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::io::Cursor;
+use std::ops::ControlFlow;
+
+/// Size of the link to the next string of a hash bucket, which precedes a name in the chunk.
+const NAME_LINK_SIZE: u64 = 6;
+/// Bound on nested template instances and BinXml values, which only self-referencing
+/// templates reach (`max_depth` rejects such records before they are walked).
+const MAX_NESTING: usize = 1024;
+/// What `evtx` renders in place of a substitution value which was already rendered.
+static SUBSTITUTED: BinXMLDeserializedTokens =
+    BinXMLDeserializedTokens::Value(BinXmlValue::NullType);
+
+/// A step of the walk over the elements of a record, in document order.
+#[derive(Debug)]
+pub enum Node<'r, 'a> {
+    /// The start of an element, at the given depth (`<Event>` is at depth 1).
+    Element(&'r BinXmlNameRef, usize),
+    /// An attribute of the element which was started at the given depth.
+    Attribute(&'r BinXmlNameRef, &'r BinXmlValue<'a>, usize),
+    /// A value in the content of the element at the given depth.
+    Text(&'r BinXmlValue<'a>, usize),
+    /// The end of the element at the given depth.
+    End(usize),
+}
+
+/// The record refers to a template which is not in its chunk's cache (as happens in files which
+/// were not closed cleanly), which only `evtx` can read, when rendering the whole record.
+#[derive(Debug)]
+pub struct MissingTemplate;
+
+/// Walks the tokens of a record, expanding template instances and nested BinXml values in place,
+/// and calls `visit` with each node until it breaks.
+///
+/// Like `evtx`, which moves a substitution value out of its template instance when rendering it,
+/// a value is only expanded where it's first referred to, and is null anywhere else.
+///
+/// Nothing is rendered along the way, so reading a few fields of a record
+/// (such as the `<System>` ones) costs a fraction of serializing it.
+pub fn walk<'r, 'a>(
+    record: &'r EvtxRecord<'a>,
+    mut visit: impl FnMut(Node<'r, 'a>) -> ControlFlow<()>,
+) -> Result<(), MissingTemplate> {
+    let no_substitutions: &[BinXMLDeserializedTokens] = &[];
+    let mut pending = vec![(record.tokens.iter(), no_substitutions)];
+    let mut substituted = HashSet::new();
+
+    let mut depth = 0_usize;
+    let mut in_start_tag = false;
+    let mut attribute = None;
+
+    while let Some((tokens, substitutions)) = pending.last_mut() {
+        let substitutions = *substitutions;
+        let token = match tokens.next() {
+            Some(token) => token,
+            None => {
+                pending.pop();
+                continue;
+            }
+        };
+
+        let node = match token {
+            BinXMLDeserializedTokens::OpenStartElement(element) => {
+                depth += 1;
+                in_start_tag = true;
+                attribute = None;
+                Some(Node::Element(&element.name, depth))
+            }
+            BinXMLDeserializedTokens::Attribute(name) if in_start_tag => {
+                attribute = Some(&name.name);
+                None
+            }
+            BinXMLDeserializedTokens::CloseStartElement => {
+                in_start_tag = false;
+                None
+            }
+            BinXMLDeserializedTokens::CloseEmptyElement
+            | BinXMLDeserializedTokens::CloseElement => {
+                in_start_tag = false;
+                let node = Node::End(depth);
+                depth = depth.saturating_sub(1);
+                Some(node)
+            }
+            BinXMLDeserializedTokens::Value(BinXmlValue::BinXmlType(tokens)) => {
+                pending.push((tokens.iter(), no_substitutions));
+                None
+            }
+            // Like `evtx`, a value without an attribute name in a start tag is ignored.
+            BinXMLDeserializedTokens::Value(value) if in_start_tag => attribute
+                .take()
+                .map(|name| Node::Attribute(name, value, depth)),
+            BinXMLDeserializedTokens::Value(value) => Some(Node::Text(value, depth)),
+            BinXMLDeserializedTokens::TemplateInstance(template) => {
+                let definition = record
+                    .chunk
+                    .template_table
+                    .get_template(template.template_def_offset)
+                    .ok_or(MissingTemplate)?;
+                pending.push((
+                    definition.tokens.iter(),
+                    template.substitution_array.as_slice(),
+                ));
+                None
+            }
+            BinXMLDeserializedTokens::Substitution(descriptor) if !descriptor.ignore => {
+                let value = substitutions
+                    .get(usize::from(descriptor.substitution_index))
+                    .filter(|&value| substituted.insert(value as *const _))
+                    .unwrap_or(&SUBSTITUTED);
+                pending.push((std::slice::from_ref(value).iter(), no_substitutions));
+                None
+            }
+            _ => None,
+        };
+
+        if let Some(node) = node {
+            if visit(node).is_break() {
+                break;
+            }
+        }
+
+        if pending.len() > MAX_NESTING {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// Resolves the name of an element or attribute, which is usually in the chunk's string cache.
+pub fn name<'a>(chunk: &'a EvtxChunk<'a>, name: &BinXmlNameRef) -> Option<Cow<'a, BinXmlName>> {
+    match chunk.string_cache.get_cached_string(name.offset) {
+        Some(name) => Some(Cow::Borrowed(name)),
+        None => {
+            let mut cursor = Cursor::new(chunk.data);
+            cursor.set_position(u64::from(name.offset) + NAME_LINK_SIZE);
+            BinXmlName::from_stream(&mut cursor).ok().map(Cow::Owned)
+        }
+    }
+}
+
+/// Whether the name of an element or attribute is `expected`.
+pub fn is_named(chunk: &EvtxChunk, name_ref: &BinXmlNameRef, expected: &str) -> bool {
+    name(chunk, name_ref).is_some_and(|name| name.as_str() == expected)
+}
+
+/// Returns the textual form of a scalar value, as `evtx` renders it,
+/// or `None` for nulls and for values which don't hold a single field.
+pub fn value_text<'v>(value: &'v BinXmlValue) -> Option<Cow<'v, str>> {
+    match value {
+        BinXmlValue::StringType(_)
+        | BinXmlValue::AnsiStringType(_)
+        | BinXmlValue::Int8Type(_)
+        | BinXmlValue::UInt8Type(_)
+        | BinXmlValue::Int16Type(_)
+        | BinXmlValue::UInt16Type(_)
+        | BinXmlValue::Int32Type(_)
+        | BinXmlValue::UInt32Type(_)
+        | BinXmlValue::Int64Type(_)
+        | BinXmlValue::UInt64Type(_)
+        | BinXmlValue::BoolType(_)
+        | BinXmlValue::GuidType(_)
+        | BinXmlValue::SizeTType(_)
+        | BinXmlValue::SidType(_)
+        | BinXmlValue::HexInt32Type(_)
+        | BinXmlValue::HexInt64Type(_) => Some(value.as_cow_str()),
+        _ => None,
+    }
+}
@@ -0,0 +1,160 @@
+use pyo3::exceptions::PyValueError;
+use pyo3::PyErr;
+
+use std::fmt::Write;
+
+/// Controls what happens to characters which are not allowed in XML 1.0 documents
+/// (such as most control characters), which `evtx` writes to XML records as is.
+#[derive(Copy, Clone, Debug, PartialOrd, PartialEq, Eq)]
+pub enum InvalidXmlChars {
+    /// Leave them in place.
+    Keep,
+    /// Replace them with U+FFFD.
+    Replace,
+    /// Replace them with `_xHHHH_` escapes (e.g. `_x0001_`), as .NET's `XmlConvert` and OOXML do.
+    /// Character references (`&#x1;`) would only be well-formed in XML 1.1.
+    /// In the text and attribute values which get escaped, text reading like an escape
+    /// has its `_` escaped (`_x005F_`), so it can be told apart.
+    Escape,
+    /// Treat records holding them as failed.
+    Error,
+}
+
+impl InvalidXmlChars {
+    pub fn from_name(name: &str) -> Result<InvalidXmlChars, PyErr> {
+        match name {
+            "keep" => Ok(InvalidXmlChars::Keep),
+            "replace" => Ok(InvalidXmlChars::Replace),
+            "escape" => Ok(InvalidXmlChars::Escape),
+            "error" => Ok(InvalidXmlChars::Error),
+            _ => Err(PyErr::new::<PyValueError, _>(format!(
+                "Unknown `xml_invalid_chars` mode `[{}]`, possible values are: keep, replace, escape, error",
+                name
+            ))),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            InvalidXmlChars::Keep => "keep",
+            InvalidXmlChars::Replace => "replace",
+            InvalidXmlChars::Escape => "escape",
+            InvalidXmlChars::Error => "error",
+        }
+    }
+
+    /// Replaces or escapes the invalid characters of the XML record `data`, according to `self`.
+    pub fn apply(self, data: String) -> String {
+        match self {
+            InvalidXmlChars::Keep | InvalidXmlChars::Error => data,
+            _ if !contains_invalid_chars(&data) => data,
+            InvalidXmlChars::Replace => data
+                .chars()
+                .map(|c| match is_allowed(c) {
+                    true => c,
+                    false => char::REPLACEMENT_CHARACTER,
+                })
+                .collect(),
+            InvalidXmlChars::Escape => {
+                let mut output = String::with_capacity(data.len());
+                for (segment, is_value) in segments(&data) {
+                    if contains_invalid_chars(segment) {
+                        escape(segment, is_value, &mut output);
+                    } else {
+                        output.push_str(segment);
+                    }
+                }
+                output
+            }
+        }
+    }
+}
+
+/// Escapes the invalid characters of `text` to `output`,
+/// along with the text reading like an escape if `text` is a value.
+fn escape(text: &str, is_value: bool, output: &mut String) {
+    for (i, c) in text.char_indices() {
+        if is_value && c == '_' && is_escape(&text[i..]) {
+            output.push_str("_x005F_");
+        } else if is_allowed(c) {
+            output.push(c);
+        } else {
+            let _ = write!(output, "_x{:04X}_", u32::from(c));
+        }
+    }
+}
+
+/// Splits an XML record into markup and values (text, CDATA sections and attribute values),
+/// paired with whether they are values.
+fn segments(data: &str) -> Vec<(&str, bool)> {
+    const CDATA_START: &str = "<![CDATA[";
+    const CDATA_END: &str = "]]>";
+
+    let mut segments = Vec::new();
+    let mut rest = data;
+    while !rest.is_empty() {
+        let text = rest.find('<').unwrap_or(rest.len());
+        if text > 0 {
+            segments.push((&rest[..text], true));
+            rest = &rest[text..];
+            continue;
+        }
+
+        if let Some(cdata) = rest.strip_prefix(CDATA_START) {
+            let end = cdata.find(CDATA_END).unwrap_or(cdata.len());
+            segments.push((CDATA_START, false));
+            segments.push((&cdata[..end], true));
+            segments.push((&cdata[end..(end + CDATA_END.len()).min(cdata.len())], false));
+            rest = &cdata[(end + CDATA_END.len()).min(cdata.len())..];
+            continue;
+        }
+
+        // A tag, made of markup around quoted attribute values.
+        let mut start = 0;
+        let mut quote = None;
+        let mut end = rest.len();
+        for (i, c) in rest.char_indices() {
+            match (quote, c) {
+                (None, '"' | '\'') => {
+                    segments.push((&rest[start..=i], false));
+                    start = i + 1;
+                    quote = Some(c);
+                }
+                (Some(q), _) if c == q => {
+                    segments.push((&rest[start..i], true));
+                    start = i;
+                    quote = None;
+                }
+                (None, '>') => {
+                    end = i + 1;
+                    break;
+                }
+                _ => {}
+            }
+        }
+        segments.push((&rest[start..end], quote.is_some()));
+        rest = &rest[end..];
+    }
+
+    segments
+}
+
+pub fn contains_invalid_chars(data: &str) -> bool {
+    !data.chars().all(is_allowed)
+}
+
+/// Whether `text` starts with an `_xHHHH_` escape.
+fn is_escape(text: &str) -> bool {
+    let bytes = text.as_bytes();
+
+    bytes.len() >= 7
+        && bytes.starts_with(b"_x")
+        && bytes[2..6].iter().all(u8::is_ascii_hexdigit)
+        && bytes[6] == b'_'
+}
+
+/// Whether `c` matches the `Char` production of XML 1.0.
+/// Surrogates can't appear in a `str`, so only the control characters and U+FFFE/U+FFFF are left out.
+fn is_allowed(c: char) -> bool {
+    matches!(c, '\t' | '\n' | '\r' | '\u{20}'..='\u{FFFD}' | '\u{10000}'..)
+}
@@ -1,9 +1,19 @@
 import pytest
 import io
+import struct
+import base64
+import hashlib
+import pickle
+import queue
+import uuid
+from xml.etree import ElementTree
 
 from pathlib import Path
-from evtx import PyEvtxParser
+import evtx
+from evtx import PyEvtxParser, PyEvtxRecord
 import json
+import re
+from datetime import datetime, timedelta
 
 SAMPLES = Path(__file__).parent.parent / 'samples'
 
@@ -13,6 +23,156 @@ def small_sample() -> str:
     return str(SAMPLES / 'Security_short_selected.evtx')
 
 
+@pytest.fixture
+def bad_checksum_sample(small_sample) -> bytes:
+    with open(small_sample, "rb") as o:
+        data = bytearray(o.read())
+
+    # Flip a reserved byte of the first chunk header, which is covered by its checksum.
+    data[4096 + 60] ^= 0xFF
+    return bytes(data)
+
+
+@pytest.fixture
+def bad_record_sample(small_sample) -> bytes:
+    with open(small_sample, "rb") as o:
+        data = bytearray(o.read())
+
+    # Replace the first BinXml token of the second record with an invalid one.
+    first_record = 4096 + 512
+    second_record = first_record + int.from_bytes(data[first_record + 4:first_record + 8], 'little')
+    data[second_record + 24] = 0xEE
+    return bytes(data)
+
+
+def deeply_nested_sample(small_sample, levels) -> bytes:
+    """A single chunk holding a single record, made of `levels` nested `<a>` elements."""
+    with open(small_sample, "rb") as o:
+        file_header = o.read(4096)
+        chunk_header = bytearray(o.read(512))
+
+    # Drop the string and template tables, the record only uses an inline name.
+    chunk_header[128:512] = bytes(384)
+
+    record_offset = 512
+    name_offset = record_offset + 24 + 4 + 11
+    open_element = b'\x01' + struct.pack('<HII', 0xFFFF, 0, name_offset)
+
+    binxml = b'\x0f\x01\x01\x00'
+    binxml += open_element + struct.pack('<IHH', 0, 0, 1) + 'a\0'.encode('utf-16-le') + b'\x02'
+    binxml += (open_element + b'\x02') * (levels - 1)
+    binxml += b'\x04' * levels + b'\x00'
+
+    size = 24 + len(binxml) + 4
+    record = b'**\x00\x00' + struct.pack('<IQQ', size, 1, 0) + binxml + struct.pack('<I', size)
+
+    struct.pack_into('<QQQQ', chunk_header, 8, 1, 1, 1, 1)
+    struct.pack_into('<II', chunk_header, 44, record_offset, record_offset + size)
+    chunk = bytes(chunk_header) + record
+    return file_header + chunk + bytes(65536 - len(chunk))
+
+
+def elements_sample(small_sample, element) -> bytes:
+    """A single chunk holding a single record, made of `element`: a tuple of a name,
+    a dict of attributes and a list of children, which are elements or strings."""
+    with open(small_sample, "rb") as o:
+        file_header = o.read(4096)
+        chunk_header = bytearray(o.read(512))
+
+    chunk_header[128:512] = bytes(384)
+
+    record_offset = 512
+    binxml = bytearray(b'\x0f\x01\x01\x00')
+
+    def offset():
+        return record_offset + 24 + len(binxml)
+
+    def name(value):
+        return struct.pack('<IHH', 0, 0, len(value)) + (value + '\0').encode('utf-16-le')
+
+    def string(value):
+        return b'\x05\x01' + struct.pack('<H', len(value)) + value.encode('utf-16-le')
+
+    def write(element):
+        tag, attributes, children = element
+        binxml.extend(b'\x41' if attributes else b'\x01')
+        binxml.extend(struct.pack('<HII', 0xFFFF, 0, offset() + 10) + name(tag))
+        if attributes:
+            start = len(binxml)
+            binxml.extend(bytes(4))
+            for i, (key, value) in enumerate(attributes.items()):
+                binxml.extend(b'\x46' if i < len(attributes) - 1 else b'\x06')
+                binxml.extend(struct.pack('<I', offset() + 4) + name(key) + string(value))
+            struct.pack_into('<I', binxml, start, len(binxml) - start - 4)
+
+        if not children:
+            binxml.extend(b'\x03')
+            return
+
+        binxml.extend(b'\x02')
+        for child in children:
+            if isinstance(child, str):
+                binxml.extend(string(child))
+            else:
+                write(child)
+        binxml.extend(b'\x04')
+
+    write(element)
+    binxml.extend(b'\x00')
+
+    size = 24 + len(binxml) + 4
+    record = b'**\x00\x00' + struct.pack('<IQQ', size, 1, 0) + bytes(binxml) + struct.pack('<I', size)
+
+    struct.pack_into('<QQQQ', chunk_header, 8, 1, 1, 1, 1)
+    struct.pack_into('<II', chunk_header, 44, record_offset, record_offset + size)
+    chunk = bytes(chunk_header) + record
+    return file_header + chunk + bytes(65536 - len(chunk))
+
+
+def repeated_template_sample(small_sample, levels, repeats) -> bytes:
+    """A single chunk holding a single record, made of `levels` nested instances of a template
+    which renders its only substitution `repeats` times."""
+    with open(small_sample, "rb") as o:
+        file_header = o.read(4096)
+        chunk_header = bytearray(o.read(512))
+
+    chunk_header[128:512] = bytes(384)
+
+    def fragment(tokens):
+        return b'\x0f\x01\x01\x00' + tokens + b'\x00'
+
+    # The record is written first, and refers to the template written after it.
+    def record_at(template_offset):
+        def instance(value_type, value):
+            return (b'\x0c\x01' + struct.pack('<III', 0, template_offset, 1)
+                    + struct.pack('<HBB', len(value), value_type, 0) + value)
+
+        value = instance(0x00, b'')
+        for _ in range(levels):
+            value = instance(0x21, fragment(value))
+
+        binxml = fragment(value)
+        size = 24 + len(binxml) + 4
+        return b'**\x00\x00' + struct.pack('<IQQ', size, 1, 0) + binxml + struct.pack('<I', size)
+
+    record_offset = 512
+    template_offset = record_offset + len(record_at(0))
+    record = record_at(template_offset)
+
+    name_offset = template_offset + 24 + 4 + 11
+    element = b'\x01' + struct.pack('<HII', 0xFFFF, 0, name_offset)
+    element += struct.pack('<IHH', 0, 0, 1) + 'a\0'.encode('utf-16-le') + b'\x02'
+    element += b'\x0d\x00\x00\x21' * repeats + b'\x04'
+    definition = fragment(element)
+    template = struct.pack('<I', 0) + bytes(16) + struct.pack('<I', len(definition)) + definition
+
+    struct.pack_into('<QQQQ', chunk_header, 8, 1, 1, 1, 1)
+    struct.pack_into('<II', chunk_header, 44, record_offset, template_offset + len(template))
+    struct.pack_into('<I', chunk_header, 384, template_offset)
+    chunk = bytes(chunk_header) + record + template
+    return file_header + chunk + bytes(65536 - len(chunk))
+
+
 def test_it_works(small_sample):
     parser = PyEvtxParser(small_sample)
     records = list(parser)
@@ -126,6 +286,17 @@ def test_it_supports_various_ascii_codecs(small_sample):
         assert '<EventID>' in records[0]['data']
 
 
+@pytest.mark.parametrize("codec", ["cp1252", "latin-1", "ISO8859_2", "utf8", "shift_jis"])
+def test_it_supports_python_codec_aliases(small_sample, codec):
+    parser = PyEvtxParser(small_sample, ansi_codec=codec)
+    assert len(list(parser.records())) == 7
+
+
+def test_it_fails_on_unknown_codec(small_sample):
+    with pytest.raises(ValueError):
+        PyEvtxParser(small_sample, ansi_codec="not-a-codec")
+
+
 def test_it_supports_various_num_threads(small_sample):
     with open(small_sample, "rb") as o:
         parser = PyEvtxParser(o, number_of_threads=1)
@@ -139,3 +310,1374 @@ def test_it_supports_various_num_threads(small_sample):
         assert '<EventID>5152</EventID>' in records[0]['data']
 
 
+
+
+def test_it_supports_compact_json_shape(small_sample):
+    parser = PyEvtxParser(small_sample)
+    records = list(parser.records_json(shape="compact"))
+    assert len(records) == 7
+
+    system = json.loads(records[0]['data'])['Event']['System']
+    assert system['Provider']['@Name'] == 'Microsoft-Windows-Security-Auditing'
+    assert '#attributes' not in system['Provider']
+
+
+def test_it_supports_winlogbeat_json_shape(small_sample):
+    parser = PyEvtxParser(small_sample)
+    records = list(parser.records_json(shape="winlogbeat"))
+    assert len(records) == 7
+
+    system = json.loads(records[0]['data'])['Event']['System']
+    assert system['Provider']['Name'] == 'Microsoft-Windows-Security-Auditing'
+    assert system['TimeCreated'].startswith('2016-06-29T15:24:34')
+
+
+def test_it_fails_on_unknown_json_shape(small_sample):
+    parser = PyEvtxParser(small_sample)
+
+    with pytest.raises(ValueError):
+        parser.records_json(shape="xml")
+
+
+def test_it_supports_byte_range(small_sample):
+    parser = PyEvtxParser(small_sample, byte_range=(0, 4097))
+    records = list(parser.records())
+    assert len(records) == 7
+
+    parser = PyEvtxParser(small_sample, byte_range=(4097, 1 << 32))
+    records = list(parser.records())
+    assert len(records) == 0
+
+
+def test_it_fails_on_inverted_byte_range(small_sample):
+    with pytest.raises(ValueError):
+        PyEvtxParser(small_sample, byte_range=(100, 0))
+
+
+def test_it_returns_time_range(small_sample):
+    parser = PyEvtxParser(small_sample)
+    oldest, newest = parser.time_range()
+
+    timestamps = sorted(r['timestamp'] for r in parser.records())
+    assert len(timestamps) == 7
+    assert oldest <= newest
+    assert oldest.tzinfo is not None
+    assert oldest.strftime('%Y-%m-%d %H:%M:%S') == timestamps[0][:19]
+    assert newest.strftime('%Y-%m-%d %H:%M:%S') == timestamps[-1][:19]
+
+
+def test_time_range_is_none_without_records(small_sample):
+    parser = PyEvtxParser(small_sample, byte_range=(0, 0))
+    assert parser.time_range() is None
+
+
+def test_it_supports_bare_records(small_sample):
+    parser = PyEvtxParser(small_sample, bare=True)
+    records = list(parser.records())
+    assert len(records) == 7
+
+    for record in records:
+        assert record['data'].startswith('<Event ')
+        assert record['data'].endswith('</Event>')
+
+    parser = PyEvtxParser(small_sample, bare=True)
+    for record in parser.records_json():
+        assert record['data'] == record['data'].strip()
+        assert json.loads(record['data'])['Event']
+
+
+def test_it_returns_event_id_histogram(small_sample):
+    parser = PyEvtxParser(small_sample)
+    histogram = parser.event_id_histogram()
+
+    assert sum(histogram.values()) == 7
+    assert list(histogram) == sorted(histogram)
+    assert histogram[5152] >= 1
+
+    by_count = parser.event_id_histogram(sort_by_count=True)
+    assert by_count == histogram
+    assert list(by_count.values()) == sorted(by_count.values(), reverse=True)
+
+    # The parser is not consumed.
+    assert len(list(parser.records())) == 7
+
+
+def test_it_ignores_checksums_by_default(bad_checksum_sample):
+    parser = PyEvtxParser(io.BytesIO(bad_checksum_sample))
+    assert len(list(parser.records())) == 7
+
+
+def test_it_warns_on_checksum_mismatch(bad_checksum_sample):
+    parser = PyEvtxParser(io.BytesIO(bad_checksum_sample), checksum_policy="warn")
+    records = parser.records()
+
+    with pytest.warns(RuntimeWarning) as warnings:
+        assert len(list(records)) == 7
+
+    assert records.checksum_mismatches == [0]
+    assert warnings[0].filename == __file__
+
+
+def test_it_raises_on_checksum_mismatch(bad_checksum_sample):
+    parser = PyEvtxParser(io.BytesIO(bad_checksum_sample), checksum_policy="error")
+
+    with pytest.raises(RuntimeError):
+        list(parser.records())
+
+
+def test_it_filters_records_by_pattern(small_sample):
+    parser = PyEvtxParser(small_sample)
+    records = list(parser.records_matching(r'<EventID>5152</EventID>'))
+    assert len(records) == 2
+
+    parser = PyEvtxParser(small_sample)
+    records = list(parser.records_matching(r'<eventid>5152</eventid>', case_insensitive=True, invert=True))
+    assert len(records) == 5
+
+    parser = PyEvtxParser(small_sample)
+    records = list(parser.records_matching(r'"EventID": 5152', output_format='json'))
+    assert len(records) == 2
+    assert json.loads(records[0]['data'])['Event']['System']['EventID'] == 5152
+
+
+def test_it_fails_on_invalid_pattern(small_sample):
+    parser = PyEvtxParser(small_sample)
+
+    with pytest.raises(ValueError):
+        parser.records_matching(r'(unclosed')
+
+
+def test_it_counts_yielded_records(small_sample):
+    parser = PyEvtxParser(small_sample)
+    records = parser.records()
+    assert records.records_yielded == 0
+
+    next(records)
+    next(records)
+    assert records.records_yielded == 2
+
+    list(records)
+    assert records.records_yielded == 7
+
+
+def test_it_supports_concatenated_files(small_sample):
+    with open(small_sample, "rb") as o:
+        data = o.read()
+
+    parser = PyEvtxParser(io.BytesIO(data * 3), multi_file=True)
+    records = list(parser.records())
+    assert len(records) == 21
+    assert [r['event_record_id'] for r in records[:7]] == [r['event_record_id'] for r in records[7:14]]
+
+    parser = PyEvtxParser(io.BytesIO(data * 3), multi_file=True)
+    assert sum(parser.event_id_histogram().values()) == 21
+
+
+def test_it_fails_on_multi_file_with_byte_range(small_sample):
+    with pytest.raises(ValueError):
+        PyEvtxParser(small_sample, multi_file=True, byte_range=(0, 1))
+
+
+def test_it_yields_typed_records(small_sample):
+    parser = PyEvtxParser(small_sample)
+    records = list(parser.records_typed())
+    assert len(records) == 7
+
+    assert isinstance(records[0], PyEvtxRecord)
+    assert records[0].event_record_id
+    assert records[0].timestamp.tzinfo is not None
+    assert '<EventID>' in records[0].data
+
+    parser = PyEvtxParser(small_sample)
+    record = next(parser.records_typed(output_format='json'))
+    assert json.loads(record.data)['Event']['System']['EventID']
+    assert record.size is None and record.binary_data is None and record.content_hash is None
+
+    options = dict(include_metadata=True, extract_binary=True, content_hash=True)
+    expected = next(PyEvtxParser(small_sample, **options).records())
+    record = next(PyEvtxParser(small_sample, **options).records_typed())
+    for key, value in expected.items():
+        if key != 'timestamp':
+            assert getattr(record, key) == value, key
+
+
+def test_it_extracts_binary_data(small_sample):
+    parser = PyEvtxParser(small_sample, extract_binary=True)
+    records = list(parser.records())
+
+    for record in records:
+        assert all(isinstance(b, bytes) for b in record['binary_data'])
+
+    parser = PyEvtxParser(small_sample)
+    assert 'binary_data' not in next(parser.records())
+
+
+def test_it_reports_build_info():
+    info = evtx.build_info()
+    assert info['version'] == evtx.__version__
+    assert info['evtx_version'].count('.') == 2
+    assert info['pyo3_version'].count('.') == 2
+    assert info['wevt_templates'] is False
+    assert evtx.has_wevt_support() is info['wevt_templates']
+
+
+def test_it_slices_records(small_sample):
+    all_ids = [r['event_record_id'] for r in PyEvtxParser(small_sample).records()]
+
+    records = PyEvtxParser(small_sample).records()[2:5]
+    assert [r['event_record_id'] for r in records] == all_ids[2:5]
+
+    records = PyEvtxParser(small_sample).records_json()[1::3]
+    assert [r['event_record_id'] for r in records] == all_ids[1::3]
+
+    records = PyEvtxParser(small_sample).records()[1:][::2][:2]
+    assert [r['event_record_id'] for r in records] == all_ids[1:][::2][:2]
+
+    with pytest.raises(ValueError):
+        PyEvtxParser(small_sample).records()[-1:]
+
+
+def test_it_reports_record_errors(bad_record_sample):
+    records = PyEvtxParser(io.BytesIO(bad_record_sample)).records()
+    assert len(list(records)) == 6
+    assert records.record_errors == 1
+
+    records = PyEvtxParser(io.BytesIO(bad_record_sample), report_record_errors=True).records()
+    entries = list(records)
+    assert len(entries) == 7
+    assert records.record_errors == 1
+
+    error = entries[1]
+    assert error['chunk_number'] == 0
+    assert error['chunk_offset'] == 4096
+    assert error['record_index'] == 1
+    assert error['previous_record_id'] == entries[0]['event_record_id']
+    assert error['error']
+
+
+def test_it_limits_nesting_depth(small_sample):
+    data = deeply_nested_sample(small_sample, 3000)
+
+    records = PyEvtxParser(io.BytesIO(data), report_record_errors=True).records()
+    entries = list(records)
+    assert records.record_errors == 1
+    assert 'max_depth' in entries[0]['error']
+
+    records = list(PyEvtxParser(io.BytesIO(data), max_depth=None).records())
+    assert records[0]['data'].count('<a>') == 3000
+
+    records = list(PyEvtxParser(io.BytesIO(deeply_nested_sample(small_sample, 10))).records())
+    assert records[0]['data'].count('<a>') == 10
+
+
+def test_it_expands_substitutions_once(small_sample):
+    # `evtx` renders a substitution value where it's first referred to only, so checking
+    # the depth of this record must not take 8 ** 40 steps either.
+    data = repeated_template_sample(small_sample, 40, 8)
+
+    records = list(PyEvtxParser(io.BytesIO(data), include_metadata=True).records())
+    assert records[0]['data'].count('<a>') == 41
+
+
+def test_it_checks_depth_before_filtering(small_sample):
+    # Every record of the sample is deeper than 2.
+    records = PyEvtxParser(small_sample, max_depth=2, report_record_errors=True).records(
+        providers=['Microsoft-Windows-Security-Auditing'], where='event_id != 0')
+    entries = list(records)
+    assert len(entries) == 7
+    assert all('max_depth' in e['error'] for e in entries)
+
+    parser = PyEvtxParser(small_sample, max_depth=2)
+    assert parser.event_id_histogram() == {}
+    assert parser.count_matching() == 0
+    assert parser.summarize()['record_count'] == 0
+
+
+def test_it_yields_records_between_ids(small_sample):
+    all_ids = [r['event_record_id'] for r in PyEvtxParser(small_sample).records()]
+
+    records = PyEvtxParser(small_sample).records_between(all_ids[2], all_ids[4])
+    assert [r['event_record_id'] for r in records] == all_ids[2:5]
+
+    records = PyEvtxParser(small_sample).records_between(all_ids[-1] + 1, all_ids[-1] + 100, output_format='json')
+    assert list(records) == []
+
+
+class FlakyStream(io.BytesIO):
+    """A stream whose reads fail now and then, like one over a dropping connection."""
+
+    def __init__(self, data, failures):
+        super().__init__(data)
+        self.failures = failures
+        self.reads = 0
+
+    def read(self, *args):
+        self.reads += 1
+        if self.reads % 5 == 0 and self.failures > 0:
+            self.failures -= 1
+            super().read(*args)  # Leave the stream somewhere else.
+            raise ConnectionResetError('connection reset')
+        return super().read(*args)
+
+    def __repr__(self):
+        return '<FlakyStream>'
+
+
+def test_it_retries_failed_reads(small_sample):
+    with open(small_sample, "rb") as o:
+        data = o.read()
+
+    parser = PyEvtxParser(FlakyStream(data, failures=2), read_retries=1)
+    assert len(list(parser.records())) == 7
+    assert parser.settings()['read_retries'] == 1
+
+    with pytest.raises(OSError, match='<FlakyStream>.*connection reset'):
+        PyEvtxParser(FlakyStream(data, failures=2))
+
+    # Once retries are exhausted, chunks which fail to be read raise the original error too.
+    stream = FlakyStream(data, failures=0)
+    parser = PyEvtxParser(stream)
+    stream.failures = 1
+    with pytest.raises(OSError, match='<FlakyStream>.*connection reset'):
+        list(parser.records())
+
+
+def test_it_fails_on_non_seekable_file_like(small_sample):
+    class Unseekable(io.BytesIO):
+        def seekable(self):
+            return False
+
+    with open(small_sample, "rb") as o:
+        stream = Unseekable(o.read())
+
+    with pytest.raises(TypeError, match='seekable'):
+        PyEvtxParser(stream)
+
+
+def test_it_works_on_bytes(small_sample):
+    with open(small_sample, "rb") as o:
+        data = o.read()
+
+    records = list(PyEvtxParser(data).records())
+    assert len(records) == 7
+    assert '<EventID>' in records[0]['data']
+
+
+def test_it_maps_records_to_ecs(small_sample):
+    parser = PyEvtxParser(small_sample)
+    records = list(parser.records_ecs())
+    assert len(records) == 7
+
+    ecs = json.loads(records[0]['data'])
+    assert ecs['@timestamp'].startswith('2016-06-29T')
+    assert ecs['event']['code'] == '5152'
+    assert ecs['host']['name'] == 'temporal'
+    assert ecs['winlog']['provider_name'] == 'Microsoft-Windows-Security-Auditing'
+    assert ecs['winlog']['record_id'] == 319457771
+    assert ecs['winlog']['event_data']['SourceAddress'] == '23.94.153.202'
+
+
+def test_it_verifies_chunk_checksums(small_sample, bad_checksum_sample):
+    chunks = list(PyEvtxParser(small_sample).verify_chunks())
+    assert len(chunks) == 1
+
+    chunk_number, stored, computed, ok = chunks[0]
+    assert chunk_number == 0
+    assert stored == computed
+    assert ok
+
+    for policy in ['ignore', 'error']:
+        parser = PyEvtxParser(io.BytesIO(bad_checksum_sample), checksum_policy=policy)
+        chunk_number, stored, computed, ok = next(parser.verify_chunks())
+        assert not ok
+        assert stored[0] != computed[0]
+        assert stored[1] == computed[1]
+
+
+def test_it_filters_by_provider(small_sample):
+    parser = PyEvtxParser(small_sample)
+    assert len(list(parser.records(providers=['microsoft-windows-security-auditing']))) == 7
+
+    parser = PyEvtxParser(small_sample)
+    assert len(list(parser.records(providers=['{54849625-5478-4994-a5ba-3e3b0328c30d}']))) == 7
+
+    parser = PyEvtxParser(small_sample)
+    assert list(parser.records(providers=['Microsoft-Windows-Sysmon'])) == []
+
+
+def test_it_dumps_record_bytes(small_sample):
+    parser = PyEvtxParser(small_sample)
+    record_id = next(parser.records())['event_record_id']
+
+    parser = PyEvtxParser(small_sample)
+    dump = parser.record_hexdump(record_id)
+    assert dump.startswith('00000000  2a 2a 00 00 ')
+    assert '|**' in dump.splitlines()[0]
+
+    with pytest.raises(KeyError):
+        parser.record_hexdump(10 ** 9)
+
+
+def test_it_reads_system_fields_like_rendered_records(small_sample):
+    fields = ['event_id', 'version', 'provider', 'provider_guid', 'channel']
+    tuples = list(PyEvtxParser(small_sample).records_tuples(fields))
+
+    expected = []
+    for record in PyEvtxParser(small_sample).records_json():
+        system = json.loads(record['data'])['Event']['System']
+        event_id = system['EventID']
+        expected.append((
+            event_id['#text'] if isinstance(event_id, dict) else event_id,
+            system['Version'],
+            system['Provider']['#attributes']['Name'],
+            system['Provider']['#attributes']['Guid'],
+            system['Channel'],
+        ))
+
+    assert tuples == expected
+
+
+def test_it_lists_schema_keys(small_sample):
+    parser = PyEvtxParser(small_sample)
+    keys = parser.schema_keys()
+
+    assert keys == sorted(set(keys))
+    assert ('54849625-5478-4994-A5BA-3E3B0328C30D', 5152, 0) in keys
+    assert sum(1 for _ in parser.records()) == 7
+
+
+def test_it_works_on_path_like(small_sample):
+    parser = PyEvtxParser(Path(small_sample))
+    assert len(list(parser.records())) == 7
+
+    class PathLike:
+        def __fspath__(self):
+            return small_sample
+
+    parser = PyEvtxParser(PathLike())
+    assert len(list(parser.records())) == 7
+
+    with pytest.raises(FileNotFoundError):
+        PyEvtxParser(Path(small_sample).with_name('missing.evtx'))
+
+
+@pytest.mark.parametrize("data", [b"", b"ElfFile\x00", b"PK\x03\x04" + bytes(8192)])
+def test_it_fails_on_invalid_files(data):
+    with pytest.raises(ValueError, match='not a valid EVTX file'):
+        PyEvtxParser(io.BytesIO(data))
+
+
+@pytest.mark.parametrize("data, container", [
+    (b"PK\x03\x04", 'zip archive'),
+    (b"\x1f\x8b\x08", 'gzip stream'),
+    (b"7z\xbc\xaf\x27\x1c", '7z archive'),
+    (b"\xd0\xcf\x11\xe0\xa1\xb1\x1a\xe1", 'OLE compound file'),
+])
+def test_it_detects_container_files(data, container):
+    with pytest.raises(ValueError, match=container + '.*extract'):
+        PyEvtxParser(io.BytesIO(data + bytes(8192)))
+
+
+def test_it_detects_byte_swapped_files(small_sample):
+    with open(small_sample, 'rb') as f:
+        data = f.read()
+
+    swapped = b''.join(data[i:i + 4][::-1] for i in range(0, 4096, 4)) + data[4096:]
+    with pytest.raises(ValueError, match='byte-swapped.*little-endian'):
+        PyEvtxParser(io.BytesIO(swapped))
+
+
+@pytest.mark.parametrize("offset, value, message", [
+    (32, struct.pack('<I', 256), 'header size 256'),
+    (32, struct.pack('>I', 128), 'byte-swapped'),
+    (40, struct.pack('<H', 64), 'header block size 64'),
+])
+def test_it_fails_on_implausible_file_headers(small_sample, offset, value, message):
+    with open(small_sample, 'rb') as f:
+        data = bytearray(f.read())
+
+    data[offset:offset + len(value)] = value
+    with pytest.raises(ValueError, match='implausible file header.*' + message):
+        PyEvtxParser(io.BytesIO(bytes(data)))
+
+
+def test_it_reads_chunks_at_the_header_block_size(small_sample):
+    with open(small_sample, 'rb') as f:
+        data = f.read()
+
+    header = bytearray(data[:4096])
+    header[40:42] = struct.pack('<H', 1024)
+    data = bytes(header[:1024]) + data[4096:]
+
+    parser = PyEvtxParser(io.BytesIO(data))
+    assert parser.chunk_offsets() == [1024]
+    assert [r['event_record_id'] for r in parser.records()] == list(range(1, 8))
+
+    parser = PyEvtxParser(io.BytesIO(data), byte_range=(1024, 1024 + 65536))
+    assert len(list(parser.records())) == 7
+
+
+def test_it_preserves_source_order_in_json(small_sample):
+    xml = next(PyEvtxParser(small_sample).records())['data']
+    record = json.loads(next(PyEvtxParser(small_sample).records_json())['data'])
+
+    system = xml[xml.index('<System>'):xml.index('</System>')]
+    elements = re.findall(r'^\s*<(\w+)', system, re.MULTILINE)[1:]
+    assert list(record['Event']['System'].keys()) == elements
+
+    provider = record['Event']['System']['Provider']['#attributes']
+    assert list(provider.keys()) == ['Name', 'Guid']
+
+
+def test_it_counts_matching_records(small_sample):
+    records = [json.loads(r['data']) for r in PyEvtxParser(small_sample).records_json()]
+    event_id = records[0]['Event']['System']['EventID']
+    expected = sum(1 for r in records if r['Event']['System']['EventID'] == event_id)
+
+    parser = PyEvtxParser(small_sample)
+    assert parser.count_matching() == 7
+    assert parser.count_matching(event_ids=[event_id]) == expected
+    assert parser.count_matching(providers=['Microsoft-Windows-Sysmon']) == 0
+
+    oldest, newest = parser.time_range()
+    assert parser.count_matching(from_time=oldest, to_time=newest) == 7
+    assert parser.count_matching(from_time=newest + timedelta(seconds=1)) == 0
+
+
+def test_it_stops_cleanly_at_a_partial_record(small_sample):
+    with open(small_sample, "rb") as o:
+        data = bytearray(o.read())
+
+    chunk = 4096
+    free_space_offset = int.from_bytes(data[chunk + 48:chunk + 52], 'little')
+
+    # Make the chunk look like it was copied while a record 8 was being written to it.
+    struct.pack_into('<Q', data, chunk + 16, 8)
+    struct.pack_into('<Q', data, chunk + 32, 8)
+    data[chunk + free_space_offset:chunk + free_space_offset + 24] = b'**\x00\x00' + struct.pack('<IQQ', 2000, 8, 0)
+    struct.pack_into('<I', data, chunk + 48, free_space_offset + 40)
+    struct.pack_into('<I', data, chunk + 120, 0x1)
+
+    records = PyEvtxParser(bytes(data), report_record_errors=True).records()
+    assert len(list(records)) == 7
+    assert records.partial_final_chunk
+    assert records.record_errors == 0
+
+    records = PyEvtxParser(small_sample).records()
+    assert len(list(records)) == 7
+    assert not records.partial_final_chunk
+
+
+def test_it_parses_chunk_bytes(small_sample):
+    with open(small_sample, "rb") as o:
+        data = o.read()
+
+    records = evtx.parse_chunk_bytes(data[4096:4096 + 65536], 'json')
+    expected = list(PyEvtxParser(small_sample).records_json())
+    assert [r['event_record_id'] for r in records] == [r['event_record_id'] for r in expected]
+    assert [r['data'] for r in records] == [r['data'] for r in expected]
+
+    with pytest.raises(ValueError):
+        evtx.parse_chunk_bytes(data[:65536])
+
+    with pytest.raises(ValueError):
+        evtx.parse_chunk_bytes(data[4096:4096 + 512])
+
+
+def test_it_includes_raw_timestamps(small_sample):
+    with open(small_sample, "rb") as o:
+        data = o.read()
+
+    # The timestamp is at offset 16 of the first record's header.
+    (filetime,) = struct.unpack_from('<Q', data, 4096 + 512 + 16)
+
+    records = list(PyEvtxParser(small_sample, include_metadata=True).records())
+    assert records[0]['raw_timestamp'] == filetime
+    assert all(isinstance(r['raw_timestamp'], int) for r in records)
+
+    assert 'raw_timestamp' not in next(PyEvtxParser(small_sample).records())
+
+
+def test_it_includes_record_sizes(small_sample):
+    with open(small_sample, "rb") as o:
+        data = o.read()
+
+    # The size is at offset 4 of the first record's header.
+    (size,) = struct.unpack_from('<I', data, 4096 + 512 + 4)
+
+    records = list(PyEvtxParser(small_sample, include_metadata=True).records())
+    assert records[0]['size'] == size
+    assert all(r['data_size'] == len(r['data'].encode('utf-8')) for r in records)
+
+    assert 'size' not in next(PyEvtxParser(small_sample).records())
+
+
+def test_it_includes_record_positions(small_sample):
+    records = list(PyEvtxParser(small_sample, include_metadata=True).records())
+    assert [(r['chunk_number'], r['record_index']) for r in records] == [(0, i) for i in range(7)]
+
+    assert 'record_index' not in next(PyEvtxParser(small_sample).records())
+
+
+def test_it_includes_correlation_activity_ids(small_sample):
+    records = list(PyEvtxParser(small_sample, include_metadata=True).records_json())
+
+    # The sample's records have an empty `<Correlation>` element.
+    assert all(r['activity_id'] is None and r['related_activity_id'] is None for r in records)
+
+    assert 'activity_id' not in next(PyEvtxParser(small_sample).records())
+
+
+def test_it_enforces_max_records(small_sample):
+    records = PyEvtxParser(small_sample, max_records=5).records()
+    assert len([next(records) for _ in range(5)]) == 5
+
+    with pytest.raises(RuntimeError) as e:
+        next(records)
+    assert e.value.args[1:] == (5, 6)
+
+    assert len(list(PyEvtxParser(small_sample, max_records=7).records())) == 7
+
+
+def test_it_lists_template_instances(small_sample):
+    parser = PyEvtxParser(small_sample)
+    instances = parser.record_template_instances(1)
+
+    # The `System` template embeds the `EventData` template.
+    assert len(instances) == 2
+    system = instances[0]['values']
+    assert 319457771 in system
+    assert 'Microsoft-Windows-Security-Auditing' in system
+    assert any(isinstance(v, datetime) for v in system)
+    assert system[-1] is None
+    assert all(isinstance(i['template_offset'], int) for i in instances)
+
+    with pytest.raises(KeyError):
+        parser.record_template_instances(10**9)
+
+    assert len(list(parser.records())) == 7
+
+
+def test_it_yields_records_with_templates(small_sample):
+    with open(small_sample, 'rb') as f:
+        data = f.read()
+
+    records = list(PyEvtxParser(small_sample).records_with_templates())
+    assert len(records) == 7
+
+    parser = PyEvtxParser(small_sample)
+    for record in records:
+        instances = parser.record_template_instances(record['event_record_id'])
+        offsets = [4096 + instance['template_offset'] + 4 for instance in instances]
+        expected = [str(uuid.UUID(bytes_le=data[o:o + 16])).upper() for o in offsets]
+        assert record['template_guids'] == expected
+
+    # The `System` template is shared by every record.
+    assert len({record['template_guids'][0] for record in records}) == 1
+    assert 'template_guids' not in next(PyEvtxParser(small_sample).records())
+
+
+def test_it_formats_timestamps_without_suffix(small_sample):
+    record = next(PyEvtxParser(small_sample).records())
+    assert record['timestamp'].endswith(' UTC')
+
+    record = next(PyEvtxParser(small_sample, timestamp_suffix=False).records())
+    assert not record['timestamp'].endswith('UTC')
+    assert datetime.fromisoformat(record['timestamp']) == datetime(2016, 6, 29, 15, 24, 34, 346000)
+
+
+def test_it_accepts_ansi_codec_fallbacks(small_sample):
+    records = list(PyEvtxParser(small_sample, ansi_codec_fallbacks=['cp1251', 'utf-8'], include_metadata=True).records())
+    assert len(records) == 7
+    assert all(r['ansi_codec'] == 'windows-1252' for r in records)
+
+    with pytest.raises(ValueError):
+        PyEvtxParser(small_sample, ansi_codec_fallbacks=['not-a-codec'])
+
+
+def test_it_falls_back_on_replacement_characters(small_sample):
+    with open(small_sample, "rb") as o:
+        data = bytearray(o.read())
+
+    # Turn the `Channel` substitution of the first record into an ANSI string,
+    # whose bytes decode to a replacement character as UTF-8 (without failing).
+    descriptors = data.index(struct.pack('<HBBHBB', 16, 15, 0, 16, 1, 0), 4096 + 512)
+    data[descriptors + 6] = 0x02
+    # The provider name (`Microsoft-Windows-Security-Auditing`) comes first.
+    provider = data.index('Auditing'.encode('utf-16-le'), descriptors)
+    channel = data.index('Security'.encode('utf-16-le'), provider)
+    data[channel:channel + 16] = b'caf\xef\xbf\xbd'.ljust(16, b'\x00')
+    data = bytes(data)
+
+    record = next(PyEvtxParser(data, ansi_codec='utf-8').records())
+    assert '<Channel>caf\ufffd</Channel>' in record['data']
+
+    parser = PyEvtxParser(data, ansi_codec='utf-8', ansi_codec_fallbacks=['cp1252'], include_metadata=True)
+    record = next(parser.records())
+    assert '<Channel>caf\xef\xbf\xbd</Channel>' in record['data']
+    assert record['ansi_codec'] == 'windows-1252'
+
+
+def test_it_lists_channels(small_sample):
+    parser = PyEvtxParser(small_sample)
+    assert parser.channels() == {'Security'}
+    assert len(list(parser.records())) == 7
+
+
+def test_it_yields_msgpack(small_sample):
+    records = list(PyEvtxParser(small_sample).records_msgpack())
+    assert len(records) == 7
+    assert all(isinstance(r, bytes) for r in records)
+
+    # A map of 3 entries, starting with `event_record_id`.
+    assert records[0][:17] == b'\x83\xafevent_record_id'
+    assert b'\xa4data' in records[0]
+    assert b'Microsoft-Windows-Security-Auditing' in records[0]
+
+    record = next(PyEvtxParser(small_sample, include_metadata=True).records_msgpack())
+    assert record[0] == 0x8a
+    assert b'\xabactivity_id\xc0' in record
+
+
+def test_it_renders_a_json_array(small_sample, tmp_path):
+    expected = [json.loads(r['data']) for r in PyEvtxParser(small_sample).records_json()]
+
+    document = PyEvtxParser(small_sample).to_json_array()
+    assert json.loads(document) == expected
+
+    path = tmp_path / 'records.json'
+    assert PyEvtxParser(small_sample).to_json_array(path) is None
+    assert json.loads(path.read_text()) == expected
+
+    assert json.loads(PyEvtxParser(small_sample, byte_range=(0, 0)).to_json_array()) == []
+
+
+def test_it_keeps_the_json_array_file_on_error(small_sample, tmp_path):
+    path = tmp_path / 'records.json'
+    path.write_text('previous')
+
+    with pytest.raises(RuntimeError):
+        PyEvtxParser(small_sample, max_records=2).to_json_array(path)
+
+    assert path.read_text() == 'previous'
+    assert [p.name for p in tmp_path.iterdir()] == ['records.json']
+
+
+def test_it_reports_settings(small_sample):
+    settings = PyEvtxParser(small_sample).settings()
+    assert settings['num_threads'] > 0
+    assert settings['ansi_codec'] == 'windows-1252'
+    assert not settings['validate_checksums']
+    assert not settings['wevt_cache']
+    assert settings['checksum_policy'] == 'ignore'
+    assert settings['max_depth'] == 128
+
+    settings = PyEvtxParser(small_sample, number_of_threads=2, ansi_codec='cp1251', checksum_policy='error').settings()
+    assert settings['num_threads'] == 2
+    assert settings['ansi_codec'] == 'windows-1251'
+    assert settings['validate_checksums']
+    assert settings['checksum_policy'] == 'error'
+
+
+def test_it_accepts_shared_parser_settings(small_sample):
+    shared = evtx.PyParserSettings().num_threads(2).ansi_codec('cp1251').validate_checksums(True)
+    assert 'num_threads=2' in repr(shared)
+
+    for _ in range(2):
+        settings = PyEvtxParser(small_sample, settings=shared).settings()
+        assert settings['num_threads'] == 2
+        assert settings['ansi_codec'] == 'windows-1251'
+        assert settings['checksum_policy'] == 'error'
+
+    settings = PyEvtxParser(small_sample, settings=shared, number_of_threads=1, checksum_policy='warn').settings()
+    assert settings['num_threads'] == 1
+    assert settings['ansi_codec'] == 'windows-1251'
+    assert settings['checksum_policy'] == 'warn'
+
+    compact = evtx.PyParserSettings(indent=False)
+    record = next(PyEvtxParser(small_sample, settings=compact).records_json())
+    assert '\n' not in record['data']
+
+    unpickled = pickle.loads(pickle.dumps(shared))
+    assert repr(unpickled) == repr(shared)
+
+    with pytest.raises(ValueError):
+        evtx.PyParserSettings().ansi_codec('nope')
+
+
+def test_it_handles_invalid_xml_chars(small_sample):
+    with open(small_sample, "rb") as o:
+        data = bytearray(o.read())
+
+    # Turn the `t` of the computer name (`temporal`) into a control character.
+    data[data.index('temporal'.encode('utf-16-le'), 4096 + 512)] = 0x01
+    data = bytes(data)
+
+    record = next(PyEvtxParser(data).records())
+    assert '<Computer>\x01emporal</Computer>' in record['data']
+
+    namespaces = {'e': 'http://schemas.microsoft.com/win/2004/08/events/event'}
+
+    record = next(PyEvtxParser(data, xml_invalid_chars='replace').records())
+    event = ElementTree.fromstring(record['data'])
+    assert event.find('e:System/e:Computer', namespaces).text == '\ufffdemporal'
+
+    record = next(PyEvtxParser(data, xml_invalid_chars='escape').records())
+    event = ElementTree.fromstring(record['data'])
+    assert event.find('e:System/e:Computer', namespaces).text == '_x0001_emporal'
+
+    # Text which reads like an escape has its `_` escaped.
+    offset = data.index('\x01emporal'.encode('utf-16-le'), 4096 + 512)
+    escaped = bytearray(data)
+    escaped[offset:offset + 16] = '_x0041_\x01'.encode('utf-16-le')
+    record = next(PyEvtxParser(bytes(escaped), xml_invalid_chars='escape').records())
+    event = ElementTree.fromstring(record['data'])
+    assert event.find('e:System/e:Computer', namespaces).text == '_x005F_x0041__x0001_'
+
+    # Unless it's in a value without invalid characters.
+    escaped[offset:offset + 16] = '_x0041_l'.encode('utf-16-le')
+    channel = escaped.index('Security'.encode('utf-16-le'), escaped.index('Auditing'.encode('utf-16-le')))
+    escaped[channel] = 0x01
+    record = next(PyEvtxParser(bytes(escaped), xml_invalid_chars='escape').records())
+    event = ElementTree.fromstring(record['data'])
+    assert event.find('e:System/e:Computer', namespaces).text == '_x0041_l'
+    assert event.find('e:System/e:Channel', namespaces).text == '_x0001_ecurity'
+
+    records = PyEvtxParser(data, xml_invalid_chars='error').records()
+    assert list(records) == []
+    assert records.record_errors == 7
+
+    # JSON output escapes control characters by itself.
+    record = next(PyEvtxParser(data, xml_invalid_chars='error').records_json())
+    assert '\\u0001emporal' in record['data']
+
+    with pytest.raises(ValueError):
+        PyEvtxParser(data, xml_invalid_chars='drop')
+
+
+def test_it_lists_substitutions(small_sample):
+    parser = PyEvtxParser(small_sample)
+    instances = parser.record_template_instances(1)
+    substitutions = parser.record_substitutions(1)
+
+    assert substitutions == [v for i in instances for v in i['values']]
+    assert 319457771 in substitutions
+
+    with pytest.raises(KeyError):
+        parser.record_substitutions(10**9)
+
+
+def test_it_extracts_json_pointers(small_sample):
+    records = list(PyEvtxParser(small_sample).records_extract(['/Event/System/EventRecordID', '/Event/System/Missing']))
+    assert len(records) == 7
+    assert records[0] == {'/Event/System/EventRecordID': 319457771, '/Event/System/Missing': None}
+
+    record = next(PyEvtxParser(small_sample).records_extract(['/Event/System/Provider'], shape='compact'))
+    assert record['/Event/System/Provider']['@Name'] == 'Microsoft-Windows-Security-Auditing'
+
+    with pytest.raises(ValueError):
+        PyEvtxParser(small_sample).records_extract(['Event/System'])
+
+
+def test_it_benchmarks_a_file(small_sample):
+    stats = evtx.benchmark(small_sample, iterations=2, number_of_threads=1)
+    assert stats['iterations'] == 2
+    assert stats['chunks'] == 1
+    assert stats['records'] == 7
+    assert stats['record_errors'] == 0
+    assert stats['number_of_threads'] == 1
+    assert stats['total_seconds'] > 0
+    assert stats['records_per_second'] > 0
+
+    assert evtx.benchmark(Path(small_sample), output_format='json')['records'] == 7
+
+
+def test_it_resumes_from_a_checkpoint(small_sample):
+    records = PyEvtxParser(small_sample).records()
+    assert records.checkpoint() is None
+
+    seen = [next(records)['event_record_id'] for _ in range(3)]
+    token = records.checkpoint()
+    rest = [r['event_record_id'] for r in records]
+
+    resumed = PyEvtxParser(small_sample).records(resume_from=token)
+    assert [r['event_record_id'] for r in resumed] == rest
+    assert len(seen + rest) == 7
+
+    resumed = PyEvtxParser(small_sample).records_json(resume_from=token)
+    assert len(list(resumed)) == 4
+
+    with open(small_sample, "rb") as o:
+        data = bytearray(o.read())
+
+    # A different first record id in the chunk header means the file was rewritten.
+    struct.pack_into('<Q', data, 4096 + 24, 1000)
+    with pytest.raises(ValueError):
+        PyEvtxParser(bytes(data)).records(resume_from=token)
+
+    with pytest.raises(ValueError):
+        PyEvtxParser(small_sample).records(resume_from=b'not a checkpoint')
+
+
+def test_it_encodes_binary_values(small_sample):
+    with open(small_sample, "rb") as o:
+        data = bytearray(o.read())
+
+    # Turn the `Channel` substitution of the first record (following the provider GUID)
+    # from a string into a binary value.
+    descriptors = data.index(struct.pack('<HBBHBB', 16, 15, 0, 16, 1, 0), 4096 + 512)
+    data[descriptors + 6] = 0x0E
+    data = bytes(data)
+
+    channel = 'Security'.encode('utf-16-le')
+
+    record = next(PyEvtxParser(data).records())
+    assert '<Channel>{}</Channel>'.format(channel.hex().upper()) in record['data']
+
+    record = next(PyEvtxParser(data, binary_encoding='base64').records())
+    assert '<Channel>{}</Channel>'.format(base64.b64encode(channel).decode()) in record['data']
+
+    record = next(PyEvtxParser(data, binary_encoding='base64', extract_binary=True).records_json())
+    assert json.loads(record['data'])['Event']['System']['Channel'] == base64.b64encode(channel).decode()
+    assert record['binary_data'] == [channel]
+
+    record = next(PyEvtxParser(data, binary_encoding='cdata').records())
+    assert '<Channel><![CDATA[{}]]></Channel>'.format(channel.hex().upper()) in record['data']
+    namespaces = {'e': 'http://schemas.microsoft.com/win/2004/08/events/event'}
+    event = ElementTree.fromstring(record['data'])
+    assert event.find('e:System/e:Channel', namespaces).text == channel.hex().upper()
+
+    record = next(PyEvtxParser(data, binary_encoding='cdata').records_json())
+    assert json.loads(record['data'])['Event']['System']['Channel'] == channel.hex().upper()
+
+    # A binary value in an attribute (the provider GUID) can't be a CDATA section.
+    with_guid = bytearray(data)
+    with_guid[descriptors + 2] = 0x0E
+    record = next(PyEvtxParser(bytes(with_guid), binary_encoding='cdata').records())
+    provider = ElementTree.fromstring(record['data']).find('e:System/e:Provider', namespaces)
+    assert len(bytes.fromhex(provider.get('Guid'))) == 16
+    assert '<![CDATA[{}]]>'.format(channel.hex().upper()) in record['data']
+
+    # Strings holding the characters marking CDATA sections leave binary values as plain hex.
+    computer = 'temporal'.encode('utf-16-le')
+    with_marks = bytearray(data)
+    offset = with_marks.index(computer, 4096 + 512)
+    with_marks[offset + 2:offset + 4] = '\ufdd0'.encode('utf-16-le')
+    record = next(PyEvtxParser(bytes(with_marks), binary_encoding='cdata').records())
+    system = ElementTree.fromstring(record['data']).find('e:System', namespaces)
+    assert system.find('e:Computer', namespaces).text == 't\ufdd0mporal'
+    assert system.find('e:Channel', namespaces).text == channel.hex().upper()
+    assert 'CDATA' not in record['data']
+
+    with pytest.raises(ValueError):
+        PyEvtxParser(data, binary_encoding='base32')
+
+
+def test_it_selects_json_sections(small_sample):
+    record = next(PyEvtxParser(small_sample).records_json(include_fields=['System']))
+    assert list(json.loads(record['data'])['Event']) == ['System']
+
+    record = next(PyEvtxParser(small_sample).records_json(exclude_fields=['EventData']))
+    event = json.loads(record['data'])['Event']
+    assert 'System' in event
+    assert 'EventData' not in event
+
+    record = next(PyEvtxParser(small_sample).records_json(shape='compact', include_fields=['EventData']))
+    assert list(json.loads(record['data'])['Event']) == ['EventData']
+
+
+def test_it_yields_record_batches(small_sample):
+    fields = ['/Event/System/EventID', '/Event/System/Computer', '/Event/System/Missing']
+    batches = list(PyEvtxParser(small_sample).records_arrow_batches(3, fields))
+
+    assert [len(batch['event_record_id']) for batch in batches] == [3, 3, 1]
+    assert list(batches[0]) == ['event_record_id', 'timestamp'] + fields
+
+    batch = batches[0]
+    assert batch['event_record_id'] == [1, 2, 3]
+    assert all(isinstance(timestamp, datetime) for timestamp in batch['timestamp'])
+    assert batch['/Event/System/Computer'] == ['temporal'] * 3
+    assert batch['/Event/System/Missing'] == [None] * 3
+    assert len({type(value) for value in batch['/Event/System/EventID']}) == 1
+
+    with pytest.raises(ValueError):
+        PyEvtxParser(small_sample).records_arrow_batches(0, fields)
+
+
+def test_it_yields_record_tuples(small_sample):
+    fields = ['event_record_id', 'timestamp', 'event_id', 'channel', 'data']
+    records = list(PyEvtxParser(small_sample).records_tuples(fields))
+    expected = list(PyEvtxParser(small_sample).records())
+
+    assert len(records) == len(expected) == 7
+    assert all(type(record) is tuple and len(record) == 5 for record in records)
+
+    event_record_id, timestamp, event_id, channel, data = records[0]
+    assert (event_record_id, timestamp, data) == (
+        expected[0]['event_record_id'], expected[0]['timestamp'], expected[0]['data'])
+    assert event_id == 5152
+    assert channel == 'Security'
+
+    records = list(PyEvtxParser(small_sample).records_tuples(['data', 'event_record_id'], 'json'))
+    expected = list(PyEvtxParser(small_sample).records_json())
+    assert records == [(r['data'], r['event_record_id']) for r in expected]
+
+    with pytest.raises(ValueError, match='Unknown field'):
+        PyEvtxParser(small_sample).records_tuples(['nope'])
+
+    with pytest.raises(ValueError, match='more than once'):
+        PyEvtxParser(small_sample).records_tuples(['data', 'data'])
+
+
+@pytest.mark.parametrize("skip", [True, False])
+def test_it_calls_the_chunk_error_handler(bad_checksum_sample, skip):
+    calls = []
+
+    def on_chunk_error(chunk_id, error):
+        calls.append((chunk_id, error))
+        return skip
+
+    parser = PyEvtxParser(io.BytesIO(bad_checksum_sample), checksum_policy='error', on_chunk_error=on_chunk_error)
+    records = parser.records()
+    assert list(records) == []
+    assert next(records, None) is None
+
+    assert len(calls) == 1
+    assert calls[0][0] == 0
+    assert 'CRC32' in calls[0][1]
+
+
+def test_it_rejects_uncallable_chunk_error_handlers(small_sample):
+    with pytest.raises(TypeError):
+        PyEvtxParser(small_sample, on_chunk_error=True)
+
+
+def test_it_reports_chunk_cache_stats(small_sample):
+    parser = PyEvtxParser(small_sample)
+    stats = parser.chunk_stats()
+
+    assert len(stats) == 1
+    assert stats[0]['chunk_number'] == 0
+    assert stats[0]['first_record_id'] == 1
+    assert stats[0]['last_record_id'] == 7
+    assert stats[0]['string_cache_entries'] > 0
+    assert stats[0]['template_cache_entries'] > 0
+
+    assert len(list(parser.records())) == 7
+
+
+def test_it_lists_chunk_offsets(small_sample):
+    with open(small_sample, "rb") as o:
+        data = o.read()
+
+    chunk = data[4096:4096 + 65536]
+    # A zeroed chunk is skipped, and the truncated one at the end doesn't fit in the file.
+    data = data[:4096] + chunk + bytes(65536) + chunk + chunk[:1000]
+
+    parser = PyEvtxParser(io.BytesIO(data))
+    offsets = parser.chunk_offsets()
+    assert offsets == [4096, 4096 + 2 * 65536]
+
+    records = [evtx.parse_chunk_bytes(data[offset:offset + 65536]) for offset in offsets]
+    assert sum(len(r) for r in records) == len(list(parser.records())) == 14
+
+    with open(small_sample, "rb") as o:
+        data = o.read()
+
+    with pytest.raises(ValueError, match='multi_file'):
+        PyEvtxParser(io.BytesIO(data + data), multi_file=True).chunk_offsets()
+
+
+def test_it_yields_record_text(small_sample):
+    records = list(PyEvtxParser(small_sample).records_text())
+    assert len(records) == 7
+
+    record = records[1]
+    assert record['event_record_id'] == 2
+    assert record['data'].splitlines() == ['S-1-5-18', 'TEMPORAL$', 'WORKGROUP', '0x3e7', 'Winlogon']
+    assert all('<' not in r['data'] for r in records)
+
+
+def test_it_parses_lists_of_buffers(small_sample):
+    with open(small_sample, "rb") as o:
+        data = o.read()
+
+    expected = list(PyEvtxParser(data).records())
+
+    # Pieces of uneven sizes, including an empty one, none of them aligned to chunks.
+    cuts = [0, 1000, 1000, 5000, 70000, len(data)]
+    pieces = [data[start:end] for start, end in zip(cuts, cuts[1:])]
+    assert list(PyEvtxParser(pieces).records()) == expected
+    assert list(PyEvtxParser(tuple(pieces)).records()) == expected
+
+    with pytest.raises(TypeError):
+        PyEvtxParser([data, 'not bytes'])
+
+
+def test_it_includes_content_hashes(small_sample):
+    with open(small_sample, "rb") as o:
+        data = o.read()
+
+    # The size is at offset 4 of the first record's header.
+    (size,) = struct.unpack_from('<I', data, 4096 + 512 + 4)
+    expected = hashlib.sha256(data[4096 + 512:4096 + 512 + size]).hexdigest()
+
+    records = list(PyEvtxParser(small_sample, content_hash=True).records())
+    assert records[0]['content_hash'] == expected
+    assert len({r['content_hash'] for r in records}) == 7
+
+    json_records = list(PyEvtxParser(small_sample, content_hash=True).records_json())
+    assert [r['content_hash'] for r in json_records] == [r['content_hash'] for r in records]
+
+    assert 'content_hash' not in next(PyEvtxParser(small_sample).records())
+
+
+def test_it_estimates_memory(small_sample):
+    with open(small_sample, "rb") as o:
+        data = o.read()
+
+    parser = PyEvtxParser(small_sample, number_of_threads=4)
+    estimate = parser.estimate_memory()
+    assert estimate['input'] == 0
+    assert estimate['num_threads'] == 4
+    assert estimate['record'] > 0
+    assert estimate['iterator_peak'] == estimate['chunk'] + estimate['record']
+    assert estimate['parallel_peak'] > estimate['iterator_peak']
+    assert len(list(parser.records())) == 7
+
+    estimate = PyEvtxParser(data, number_of_threads=1).estimate_memory()
+    assert estimate['input'] == len(data)
+    assert estimate['iterator_peak'] > len(data)
+
+
+def test_it_transforms_records(small_sample):
+    records = list(PyEvtxParser(small_sample).records(transform=len))
+    expected = list(PyEvtxParser(small_sample).records())
+    assert [r['data'] for r in records] == [len(r['data']) for r in expected]
+
+    def fail(data):
+        raise KeyError('transform failed')
+
+    with pytest.raises(KeyError):
+        next(PyEvtxParser(small_sample).records(transform=fail))
+
+
+def test_it_skips_records_without_payload(small_sample):
+    with open(small_sample, "rb") as o:
+        data = bytearray(o.read())
+
+    # Turn the 5 `EventData` substitutions of the second record into null values.
+    descriptors = data.index(struct.pack('<I' + 'HBB' * 5, 5, 12, 19, 0, 18, 1, 0, 18, 1, 0, 8, 21, 0, 16, 1, 0)) + 4
+    for i in range(5):
+        data[descriptors + 4 * i + 2] = 0
+    data = bytes(data)
+
+    assert '<Data Name="SubjectUserSid"></Data>' in list(PyEvtxParser(data).records())[1]['data']
+
+    records = list(PyEvtxParser(data, skip_empty=True).records())
+    assert [r['event_record_id'] for r in records] == [1, 3, 4, 5, 6, 7]
+    assert len(list(PyEvtxParser(small_sample, skip_empty=True).records())) == 7
+
+
+def test_it_summarizes_files(small_sample, bad_checksum_sample):
+    parser = PyEvtxParser(small_sample)
+    summary = parser.summarize()
+
+    assert summary['record_count'] == 7
+    assert summary['time_range'] == PyEvtxParser(small_sample).time_range()
+    assert summary['event_id_histogram'] == PyEvtxParser(small_sample).event_id_histogram()
+    assert summary['providers'] == ['Microsoft-Windows-Security-Auditing']
+    assert summary['channels'] == sorted(PyEvtxParser(small_sample).channels())
+    assert summary['bad_chunks'] == []
+    assert len(list(parser.records())) == 7
+
+    summary = PyEvtxParser(bad_checksum_sample).summarize()
+    assert [chunk_number for chunk_number, _ in summary['bad_chunks']] == [0]
+    assert summary['record_count'] == 7
+
+
+def test_it_keeps_records_without_rendering_info_intact(small_sample):
+    # The sample has no forwarded events, so leaving out `RenderingInfo` changes nothing.
+    assert list(PyEvtxParser(small_sample, rendering_info=False).records()) == list(PyEvtxParser(small_sample).records())
+    assert list(PyEvtxParser(small_sample, rendering_info=False).records_json()) == list(PyEvtxParser(small_sample).records_json())
+    assert not PyEvtxParser(small_sample, rendering_info=False).settings()['rendering_info']
+
+
+def test_it_leaves_out_rendering_info(small_sample):
+    sample = elements_sample(small_sample, ('Event', {}, [
+        ('System', {}, []),
+        ('EventData', {}, [('Data', {'Name': 'RenderingInfo'}, ['<RenderingInfo>'])]),
+        ('RenderingInfo', {'Culture': 'en-US'}, [('Message', {}, ['A message'])]),
+    ]))
+
+    kept = next(PyEvtxParser(io.BytesIO(sample)).records())
+    assert [e.tag for e in ElementTree.fromstring(kept['data'])] == ['System', 'EventData', 'RenderingInfo']
+
+    record = next(PyEvtxParser(io.BytesIO(sample), rendering_info=False).records())
+    event = ElementTree.fromstring(record['data'])
+    assert [e.tag for e in event] == ['System', 'EventData']
+    assert event.find('EventData/Data').text == '<RenderingInfo>'
+    assert record['data'] == re.sub(r'\n *<RenderingInfo.*</RenderingInfo>', '', kept['data'], flags=re.S)
+
+    record = next(PyEvtxParser(io.BytesIO(sample), rendering_info=False).records_json())
+    assert list(json.loads(record['data'])['Event']) == ['System', 'EventData']
+
+
+def test_it_parses_windows_of_the_input(small_sample):
+    with open(small_sample, "rb") as o:
+        data = o.read()
+
+    image = b'\xAA' * 1000 + data + b'\xBB' * 500
+    expected = list(PyEvtxParser(data).records())
+
+    assert list(PyEvtxParser(image, window=(1000, len(data))).records()) == expected
+    assert list(PyEvtxParser(io.BytesIO(image), window=(1000, len(data)), byte_range=(0, 4096 + 65536)).records()) == expected
+
+    with pytest.raises(ValueError, match='not a valid EVTX file'):
+        PyEvtxParser(image)
+
+    with pytest.raises(ValueError, match='window'):
+        PyEvtxParser(image, window=(1000, len(data) + 1000))
+
+
+def test_it_yields_only_failed_records(small_sample, bad_record_sample):
+    assert list(PyEvtxParser(small_sample).records_failed()) == []
+
+    records = PyEvtxParser(io.BytesIO(bad_record_sample)).records_failed()
+    errors = list(records)
+    assert len(errors) == 1
+    assert errors[0]['record_index'] == 1
+    assert errors[0]['error']
+    assert records.record_errors == 1
+
+    errors = list(PyEvtxParser(small_sample, max_depth=2).records_failed('json'))
+    assert len(errors) == 7
+    assert all('max_depth' in e['error'] for e in errors)
+
+
+def test_it_streams_records_to_a_queue(small_sample):
+    q = queue.Queue(maxsize=2)
+    stream = PyEvtxParser(small_sample).stream_to_queue(q)
+
+    records = []
+    while True:
+        record = q.get(timeout=10)
+        if record is None:
+            break
+        records.append(record)
+
+    stream.join()
+    assert not stream.running
+    assert records == list(PyEvtxParser(small_sample).records_json())
+
+
+def test_it_stops_streaming_records(small_sample):
+    q = queue.Queue(maxsize=1)
+    stream = PyEvtxParser(small_sample).stream_to_queue(q, 'xml')
+
+    assert q.get(timeout=10)['event_record_id'] == 1
+    stream.stop()
+    assert not stream.running
+    assert q.qsize() <= 1
+
+
+def test_it_waits_for_the_stream_when_dropped(small_sample):
+    q = queue.Queue(maxsize=1)
+    stream = PyEvtxParser(small_sample).stream_to_queue(q)
+    assert q.get(timeout=10)['event_record_id'] == 1
+
+    # The thread exited once dropped, so it can't put records in the room made for them.
+    del stream
+    assert q.qsize() <= 1
+    while q.qsize():
+        q.get_nowait()
+    with pytest.raises(queue.Empty):
+        q.get(timeout=0.5)
+
+
+def test_it_raises_queue_errors_from_the_stream(small_sample):
+    class ClosedQueue:
+        def put(self, item, block=True, timeout=None):
+            raise ValueError('queue is closed')
+
+    stream = PyEvtxParser(small_sample).stream_to_queue(ClosedQueue())
+    with pytest.raises(ValueError, match='queue is closed'):
+        stream.join()
+    assert not stream.running
+
+    class OldQueue:
+        def put(self, item):
+            pass
+
+    stream = PyEvtxParser(small_sample).stream_to_queue(OldQueue())
+    with pytest.raises(TypeError):
+        stream.join()
+
+    with pytest.raises(TypeError, match='put'):
+        PyEvtxParser(small_sample).stream_to_queue([])
+
+
+def test_it_filters_records_with_expressions(small_sample):
+    records = list(PyEvtxParser(small_sample).records())
+    event_ids = PyEvtxParser(small_sample).event_id_histogram()
+    event_id = next(iter(event_ids))
+
+    def ids(expression):
+        return [r['event_record_id'] for r in PyEvtxParser(small_sample).records(where=expression)]
+
+    assert len(ids("event_id == {}".format(event_id))) == event_ids[event_id]
+    assert len(ids("event_id != {}".format(event_id))) == 7 - event_ids[event_id]
+    assert ids("event_record_id in (2, 4) or event_record_id == 7") == [2, 4, 7]
+    assert ids("not (event_record_id not in (1, 2)) and channel == 'Security'") == [1, 2]
+    assert ids("provider == \"Microsoft-Windows-Security-Auditing\" AND event_record_id == 3") == [3]
+    assert ids("provider_guid == '{54849625-5478-4994-a5ba-3e3b0328c30d}' and event_record_id == 3") == [3]
+    assert ids("provider == 'Security-Auditing'") == []
+    assert len(ids("channel == 'Security'")) == len(records)
+
+    for expression in ["event_id ==", "event_id == 'x'", "channel == 1", "level == 4", "event_id = 1", "(event_id == 1", "event_id == 1 event_id"]:
+        with pytest.raises(ValueError, match='Invalid filter expression'):
+            PyEvtxParser(small_sample).records(where=expression)
+
+
+def test_it_filters_records_by_keywords(small_sample):
+    def ids(**kwargs):
+        return [r['event_record_id'] for r in PyEvtxParser(small_sample).records(**kwargs)]
+
+    assert ids(keywords_mask=0x0020000000000000) == [2]
+    assert ids(keywords_mask=0x0010000000000000) == [1, 3, 4, 5, 6, 7]
+    assert ids(keywords_mask=0x8000000000000000) == list(range(1, 8))
+    assert ids(keywords_mask=0x1) == []
+    assert ids(keywords_mask=0x0010000000000000, where="event_id == 4625") == [4]
+
+
+def test_it_filters_json_records(small_sample):
+    def ids(**kwargs):
+        return [r['event_record_id'] for r in PyEvtxParser(small_sample).records_json(**kwargs)]
+
+    assert ids(keywords_mask=0x0020000000000000) == [2]
+    assert ids(where="event_id == 4625") == [4]
+    assert ids(providers=['microsoft-windows-security-auditing'], where="event_record_id in (3, 5)") == [3, 5]
+    assert ids(providers=['Other-Provider']) == []
+
+    with pytest.raises(ValueError, match='Invalid filter expression'):
+        PyEvtxParser(small_sample).records_json(where='event_id ==')
+
+
+def test_it_reports_records_skipped_by_scans(small_sample, bad_record_sample):
+    parser = PyEvtxParser(small_sample)
+    assert parser.record_errors == 0
+
+    parser.event_id_histogram()
+    assert parser.record_errors == 0
+    assert parser.summarize()['record_errors'] == 0
+
+    parser = PyEvtxParser(io.BytesIO(bad_record_sample))
+    assert sum(parser.event_id_histogram().values()) == 6
+    assert parser.record_errors == 1
+
+    parser = PyEvtxParser(small_sample, max_depth=2)
+    assert parser.count_matching() == 0
+    assert parser.record_errors == 7
+
+    summary = parser.summarize()
+    assert (summary['record_count'], summary['record_errors']) == (0, 7)